@@ -1,11 +1,78 @@
 use crate::{
-    parse::{FuncKind, Node, NodeKind, Program, ScopeKind},
-    ty::TypeKind,
+    parse::{
+        Attrs, Binary, Func, FuncKind, InitData, Node, NodeKind, Program, Scope, ScopeKind, Storage,
+    },
+    ty::{Member, Type, TypeKind},
 };
 use anyhow::{anyhow, Result};
-use std::fmt::Write;
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    fmt::{self, Write},
+};
+
+// Assembly being generated, with the number of bytes it assembles to so far
+#[derive(Default)]
+struct Asm {
+    text: String,
+    size: usize,
+    line_start: usize, // Index in text of the line being written
+}
+
+impl Write for Asm {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.text.push_str(s);
+        while let Some(len) = self.text[self.line_start..].find('\n') {
+            let line = &self.text[self.line_start..self.line_start + len];
+            self.size += line_size(line).ok_or(fmt::Error)?;
+            self.line_start += len + 1;
+        }
+        Ok(())
+    }
+}
+
+// Bytes a line of assembly takes: nothing for a label or a comment, one for
+// each operand of .byte and two for an instruction. None for other directives.
+fn line_size(line: &str) -> Option<usize> {
+    let line = line.split([';', '#']).next().unwrap();
+    let line = line.split("//").next().unwrap().trim();
+    // A label may be followed by a statement on the same line
+    let line = match line.split_once(':') {
+        Some((label, rest)) if !label.is_empty() && label.chars().all(is_label_char) => rest.trim(),
+        _ => line,
+    };
+    if line.is_empty() {
+        Some(0)
+    } else if let Some(operands) = line.strip_prefix(".byte") {
+        Some(operands.split(',').count())
+    } else if line.starts_with('.') {
+        None
+    } else {
+        Some(2)
+    }
+}
+
+fn is_label_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '.'
+}
+
+// Lines of an asm template, which must be sized to keep data aligned
+fn asm_lines(asm: &str) -> Result<Vec<&str>> {
+    let lines: Vec<&str> = asm.lines().filter(|line| !line.trim().is_empty()).collect();
+    match lines.iter().find(|line| line_size(line).is_none()) {
+        Some(line) => Err(anyhow!("Unknown directive in asm: {}", line.trim())),
+        None => Ok(lines),
+    }
+}
 
 pub fn codegen(buf: &mut String, program: &Program) -> Result<()> {
+    let mut asm = Asm::default();
+    gen_program(&mut asm, program)?;
+    buf.push_str(&asm.text);
+    Ok(())
+}
+
+fn gen_program(buf: &mut Asm, program: &Program) -> Result<()> {
     let funcs = &program.funcs;
     let globals = &program.globals;
 
@@ -14,7 +81,7 @@ pub fn codegen(buf: &mut String, program: &Program) -> Result<()> {
     match init_func {
         Some(func) => {
             writeln!(buf, "init:")?;
-            if !func.is_naked {
+            if !func.attrs.is_naked {
                 writeln!(buf, "  push ra")?;
                 writeln!(buf, "  push fp")?;
                 writeln!(buf, "  rsp fp")?;
@@ -41,9 +108,10 @@ pub fn codegen(buf: &mut String, program: &Program) -> Result<()> {
             }
         }
         None => {
+            // The stack starts word aligned so that locals are aligned too
             writeln!(buf, "init:")?;
-            writeln!(buf, "  lil a0, 0xffff@l")?;
-            writeln!(buf, "  lih a1, 0xffff@h")?;
+            writeln!(buf, "  lil a0, 0xfffe@l")?;
+            writeln!(buf, "  lih a1, 0xfffe@h")?;
             writeln!(buf, "  or a0, a1")?;
             writeln!(buf, "  wsp a0")?;
             writeln!(buf, "  mov fp, a0")?;
@@ -55,7 +123,7 @@ pub fn codegen(buf: &mut String, program: &Program) -> Result<()> {
     match main_func {
         Some(func) => {
             writeln!(buf, "main:")?;
-            if !func.is_naked {
+            if !func.attrs.is_naked {
                 writeln!(buf, "  push ra")?;
                 writeln!(buf, "  push fp")?;
                 writeln!(buf, "  rsp fp")?;
@@ -86,85 +154,274 @@ pub fn codegen(buf: &mut String, program: &Program) -> Result<()> {
         }
     }
 
-    // Generate other functions
-    for func in funcs {
-        if func.kind == FuncKind::Other {
-            writeln!(buf, "{}:", func.name)?;
-            if !func.is_naked {
-                writeln!(buf, "  push ra")?;
-                writeln!(buf, "  push fp")?;
-                writeln!(buf, "  rsp fp")?;
-                if func.local_offset <= 31 {
-                    writeln!(buf, "  addi t0, zero, {}", func.local_offset)?;
-                } else if func.local_offset <= 255 {
-                    writeln!(buf, "  lil t0, 0x{:04x}@l", func.local_offset)?;
-                } else {
-                    writeln!(buf, "  lil t0, 0x{:04x}@l", func.local_offset)?;
-                    writeln!(buf, "  lih t1, 0x{:04x}@h", func.local_offset)?;
-                    writeln!(buf, "  or t0, t1")?;
-                }
-                writeln!(buf, "  mov t1, fp")?;
-                writeln!(buf, "  sub t1, t0")?;
-                writeln!(buf, "  wsp t1")?;
-            }
-
-            let mut arg_offset = 0;
-            for (i, arg) in func.args.iter().enumerate() {
-                if arg.ty.clone().unwrap().kind == TypeKind::Char {
-                    arg_offset += 1;
-                    writeln!(buf, "  sh a{}, fp, -{}", i, arg_offset)?;
-                } else {
-                    arg_offset += 2;
-                    writeln!(buf, "  sw a{}, fp, -{}", i, arg_offset)?;
-                }
-            }
+    // Generate other functions. File-scope asm stays in between them.
+    for func in funcs.iter().filter(|f| f.attrs.section.is_none()) {
+        match func.kind {
+            FuncKind::Other => gen_func(buf, func)?,
+            FuncKind::Asm => gen_file_asm(buf, func)?,
+            _ => {}
+        }
+    }
 
-            for node in &func.nodes {
-                match node.kind {
-                    NodeKind::Null => {}
-                    _ => {
-                        gen(buf, node)?;
-                    }
-                }
+    // Pool string literals. A string that equals another one or is a suffix
+    // of it is emitted as a label inside the longer one.
+    let mut strs: Vec<&Scope> = globals
+        .iter()
+        .filter(|g| g.kind == ScopeKind::Str)
+        .collect();
+    strs.sort_by_key(|s| Reverse(s.str.clone().unwrap().chars().count()));
+    let mut pool: HashMap<String, (String, u16)> = HashMap::new();
+    let mut owners: Vec<&Scope> = Vec::new();
+    for s in strs {
+        let str = s.str.clone().unwrap();
+        let owner = owners
+            .iter()
+            .find(|o| o.str.clone().unwrap().ends_with(&str));
+        match owner {
+            Some(o) => {
+                let offset = o.str.clone().unwrap().chars().count() - str.chars().count();
+                pool.insert(s.name.clone(), (o.name.clone(), offset as u16));
             }
+            None => owners.push(s),
         }
     }
+    let resolve = |label: &str| pool.get(label).cloned().unwrap_or((label.to_string(), 0));
 
-    // Generate global variables
+    // Labels pointing into the middle of global variables: (base, offset, label)
+    let mut offset_labels = Vec::new();
     for global in globals {
-        match global.kind {
-            ScopeKind::Var => {
-                writeln!(buf, "{}:", global.name)?;
-                for _ in 0..global.ty.clone().unwrap().size {
-                    writeln!(buf, "  .byte 0x00")?;
+        for data in global.init.iter().flatten() {
+            if let InitData::Label(label, offset) = data {
+                if *offset != 0 {
+                    let (base, base_offset) = resolve(label);
+                    offset_labels.push((base, base_offset + offset, offset_label(label, *offset)));
                 }
             }
-            ScopeKind::Str => {
-                writeln!(buf, "{}:", global.name)?;
-                for c in global.str.clone().unwrap().chars() {
-                    writeln!(buf, "  .byte 0x{:02x}", c as u8)?;
+        }
+    }
+    for (name, (base, offset)) in &pool {
+        offset_labels.push((base.clone(), *offset, name.clone()));
+    }
+    offset_labels.sort();
+    offset_labels.dedup();
+
+    // Generate global variables. Constant data and string literals are grouped
+    // in front of the writable data so that they can be placed in ROM.
+    let is_rodata = |global: &Scope| {
+        global.kind == ScopeKind::Str || global.ty.as_ref().is_some_and(|ty| ty.is_const_data())
+    };
+    for (section, read_only) in [("__rodata", true), ("__data", false)] {
+        writeln!(buf, "{}:", section)?;
+        let globals = globals
+            .iter()
+            .filter(|g| is_rodata(g) == read_only && g.attrs.section.is_none());
+        for global in globals {
+            match global.kind {
+                ScopeKind::Var => gen_var(buf, global, &offset_labels)?,
+                ScopeKind::Str if !pool.contains_key(&global.name) => {
+                    let data: Vec<InitData> = global
+                        .str
+                        .clone()
+                        .unwrap()
+                        .chars()
+                        .map(|c| InitData::Byte(c as u8))
+                        .collect();
+                    gen_data(buf, &global.name, &data, &offset_labels)?;
                 }
+                _ => {}
             }
-            _ => {}
+        }
+    }
+
+    // Functions and variables given a section follow the data, grouped under
+    // a label of the section name in the order the sections first appear.
+    // init and main stay in front.
+    let others = funcs.iter().filter(|f| f.kind == FuncKind::Other);
+    let vars = globals
+        .iter()
+        .filter(|g| g.kind == ScopeKind::Var && g.storage != Storage::Extern);
+    let mut sections: Vec<&str> = Vec::new();
+    let named = others
+        .clone()
+        .map(|f| &f.attrs)
+        .chain(vars.clone().map(|g| &g.attrs));
+    for section in named.filter_map(|attrs| attrs.section.as_deref()) {
+        if !sections.contains(&section) {
+            sections.push(section);
+        }
+    }
+    for section in sections {
+        writeln!(buf, "{}:", section)?;
+        let in_section = |attrs: &Attrs| attrs.section.as_deref() == Some(section);
+        for func in others.clone().filter(|f| in_section(&f.attrs)) {
+            gen_func(buf, func)?;
+        }
+        for global in vars.clone().filter(|g| in_section(&g.attrs)) {
+            gen_var(buf, global, &offset_labels)?;
         }
     }
 
     // Generate heap tag
+    gen_align(buf, 2)?;
     writeln!(buf, "heap:")?;
 
     Ok(())
 }
 
-fn gen(buf: &mut String, node: &Node) -> Result<()> {
+fn gen_func(buf: &mut Asm, func: &Func) -> Result<()> {
+    // Instructions are word aligned, even after file-scope asm of an odd size
+    gen_align(buf, func.attrs.aligned.unwrap_or(2).max(2))?;
+    writeln!(buf, "{}:", func.name)?;
+    if !func.attrs.is_naked {
+        writeln!(buf, "  push ra")?;
+        writeln!(buf, "  push fp")?;
+        writeln!(buf, "  rsp fp")?;
+        if func.local_offset <= 31 {
+            writeln!(buf, "  addi t0, zero, {}", func.local_offset)?;
+        } else if func.local_offset <= 255 {
+            writeln!(buf, "  lil t0, 0x{:04x}@l", func.local_offset)?;
+        } else {
+            writeln!(buf, "  lil t0, 0x{:04x}@l", func.local_offset)?;
+            writeln!(buf, "  lih t1, 0x{:04x}@h", func.local_offset)?;
+            writeln!(buf, "  or t0, t1")?;
+        }
+        writeln!(buf, "  mov t1, fp")?;
+        writeln!(buf, "  sub t1, t0")?;
+        writeln!(buf, "  wsp t1")?;
+    }
+
+    // A naked function has no frame to keep its arguments in
+    let args = if func.attrs.is_naked {
+        &[][..]
+    } else {
+        &func.args[..]
+    };
+    for (i, arg) in args.iter().enumerate() {
+        if let NodeKind::Var(var) = &arg.kind {
+            match arg.ty.clone().unwrap().kind {
+                TypeKind::Char => writeln!(buf, "  sh a{}, fp, -{}", i, var.offset)?,
+                TypeKind::Struct => {}
+                _ => writeln!(buf, "  sw a{}, fp, -{}", i, var.offset)?,
+            }
+        }
+    }
+
+    // A struct argument is passed by its address and copied here
+    let is_struct = |arg: &Node| arg.ty.clone().unwrap().kind == TypeKind::Struct;
+    if let Some(last) = args.iter().rposition(is_struct) {
+        for i in (0..=last).rev() {
+            writeln!(buf, "  push a{}", i)?;
+        }
+        for arg in &args[..=last] {
+            writeln!(buf, "  pop a1")?;
+            if is_struct(arg) {
+                gen_lval(buf, arg)?;
+                writeln!(buf, "  pop a0")?;
+                gen_memcpy(buf, &arg.ty.clone().unwrap())?;
+            }
+        }
+    }
+
+    for node in &func.nodes {
+        match node.kind {
+            NodeKind::Null => {}
+            _ => {
+                gen(buf, node)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// The template of a file-scope asm as it is, so that its labels stay at column 0
+fn gen_file_asm(buf: &mut Asm, func: &Func) -> Result<()> {
+    for node in &func.nodes {
+        if let NodeKind::Asm(n) = &node.kind {
+            for line in asm_lines(&n.asm)? {
+                writeln!(buf, "{}", line)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// A global variable. extern declarations have their storage elsewhere.
+fn gen_var(buf: &mut Asm, global: &Scope, offset_labels: &[(String, u16, String)]) -> Result<()> {
+    if global.storage == Storage::Extern {
+        return Ok(());
+    }
+    let ty = global.ty.clone().unwrap();
+    let data = match &global.init {
+        Some(init) => init.clone(),
+        None => vec![InitData::Byte(0); ty.size as usize],
+    };
+    gen_align(buf, ty.align.max(global.attrs.aligned.unwrap_or(1)))?;
+    gen_data(buf, &global.name, &data, offset_labels)
+}
+
+// Pad with zero bytes up to a multiple of align. The program is loaded at an
+// address aligned at least that much, so the offset from the start of the
+// output is enough.
+fn gen_align(buf: &mut Asm, align: u16) -> Result<()> {
+    let align = align as usize;
+    for _ in 0..(align - buf.size % align) % align {
+        writeln!(buf, "  .byte 0x00")?;
+    }
+    Ok(())
+}
+
+fn gen_data(
+    buf: &mut Asm,
+    name: &str,
+    data: &[InitData],
+    offset_labels: &[(String, u16, String)],
+) -> Result<()> {
+    writeln!(buf, "{}:", name)?;
+    let mut offset = 0;
+    for data in data.iter().map(Some).chain([None]) {
+        for (base, label_offset, label) in offset_labels {
+            if base == name && *label_offset == offset {
+                writeln!(buf, "{}:", label)?;
+            }
+        }
+        match data {
+            Some(InitData::Byte(b)) => {
+                writeln!(buf, "  .byte 0x{:02x}", b)?;
+                offset += 1;
+            }
+            Some(InitData::Label(label, label_offset)) => {
+                let label = offset_label(label, *label_offset);
+                writeln!(buf, "  .byte {}@l", label)?;
+                writeln!(buf, "  .byte {}@h", label)?;
+                offset += 2;
+            }
+            None => {}
+        }
+    }
+    Ok(())
+}
+
+fn offset_label(label: &str, offset: u16) -> String {
+    if offset == 0 {
+        label.to_string()
+    } else {
+        format!("__{}_{}", label, offset)
+    }
+}
+
+fn gen(buf: &mut Asm, node: &Node) -> Result<()> {
     match &node.kind {
         NodeKind::Num(n) => {
-            if n.val <= 31 {
+            let val = n.val as u16;
+            if (0..=31).contains(&n.val) {
                 writeln!(buf, "  addi a0, zero, {}", n.val)?;
-            } else if n.val <= 255 {
-                writeln!(buf, "  lil a0, 0x{:04x}@l", n.val)?;
+            } else if (-31..0).contains(&n.val) {
+                writeln!(buf, "  subi a0, zero, {}", -n.val)?;
+            } else if val <= 255 {
+                writeln!(buf, "  lil a0, 0x{:04x}@l", val)?;
             } else {
-                writeln!(buf, "  lil a0, 0x{:04x}@l", n.val)?;
-                writeln!(buf, "  lih a1, 0x{:04x}@h", n.val)?;
+                writeln!(buf, "  lil a0, 0x{:04x}@l", val)?;
+                writeln!(buf, "  lih a1, 0x{:04x}@h", val)?;
                 writeln!(buf, "  or a0, a1")?;
             }
             writeln!(buf, "  push a0")?;
@@ -217,6 +474,22 @@ fn gen(buf: &mut String, node: &Node) -> Result<()> {
             writeln!(buf, "  addi a0, a0, 1")?;
             writeln!(buf, "  push a0")?;
         }
+        NodeKind::Div(n) if node.ty.clone().unwrap().is_uint() => {
+            gen(buf, &n.left)?;
+            gen(buf, &n.right)?;
+            writeln!(buf, "  pop a1")?;
+            writeln!(buf, "  pop a0")?;
+            gen_udivmod(buf)?;
+            writeln!(buf, "  push a0")?;
+        }
+        NodeKind::Mod(n) if node.ty.clone().unwrap().is_uint() => {
+            gen(buf, &n.left)?;
+            gen(buf, &n.right)?;
+            writeln!(buf, "  pop a1")?;
+            writeln!(buf, "  pop a0")?;
+            gen_udivmod(buf)?;
+            writeln!(buf, "  push t0")?;
+        }
         NodeKind::Div(n) => {
             gen(buf, &n.left)?;
             gen(buf, &n.right)?;
@@ -336,6 +609,13 @@ fn gen(buf: &mut String, node: &Node) -> Result<()> {
             writeln!(buf, "  mov a0, zero")?;
             writeln!(buf, "  push a0 ")?;
         }
+        NodeKind::Neg(n) => {
+            gen(buf, &n.unary)?;
+            writeln!(buf, "  pop a1")?;
+            writeln!(buf, "  mov a0, zero")?;
+            writeln!(buf, "  sub a0, a1")?;
+            writeln!(buf, "  push a0")?;
+        }
         NodeKind::BitNot(n) => {
             gen(buf, &n.unary)?;
             writeln!(buf, "  pop a0")?;
@@ -397,7 +677,11 @@ fn gen(buf: &mut String, node: &Node) -> Result<()> {
             writeln!(buf, "  pop a1")?;
             writeln!(buf, "  pop a0")?;
             writeln!(buf, "  addi t0, zero, 1")?;
-            writeln!(buf, "  blt a0, a1, 4")?;
+            if is_unsigned_cmp(n) {
+                writeln!(buf, "  bltu a0, a1, 4")?;
+            } else {
+                writeln!(buf, "  blt a0, a1, 4")?;
+            }
             writeln!(buf, "  subi t0, t0, 1")?;
             writeln!(buf, "  mov a0, t0")?;
             writeln!(buf, "  push a0")?;
@@ -407,9 +691,15 @@ fn gen(buf: &mut String, node: &Node) -> Result<()> {
             gen(buf, &n.right)?;
             writeln!(buf, "  pop a1")?;
             writeln!(buf, "  pop a0")?;
-            writeln!(buf, "  addi t0, zero, 1")?;
-            writeln!(buf, "  bge a1, a0, 4")?;
-            writeln!(buf, "  subi t0, t0, 1")?;
+            if is_unsigned_cmp(n) {
+                writeln!(buf, "  mov t0, zero")?;
+                writeln!(buf, "  bltu a1, a0, 4")?;
+                writeln!(buf, "  addi t0, zero, 1")?;
+            } else {
+                writeln!(buf, "  addi t0, zero, 1")?;
+                writeln!(buf, "  bge a1, a0, 4")?;
+                writeln!(buf, "  subi t0, t0, 1")?;
+            }
             writeln!(buf, "  mov a0, t0")?;
             writeln!(buf, "  push a0")?;
         }
@@ -479,14 +769,16 @@ fn gen(buf: &mut String, node: &Node) -> Result<()> {
             writeln!(buf, "  pop ra")?;
             writeln!(buf, "  jalr zero, ra, 0")?;
         }
+        // The value of an array, a struct or a function is its address
         NodeKind::Var(_) | NodeKind::MemAccess(_) => {
             gen_lval(buf, node)?;
-            if node.ty.clone().unwrap().kind != TypeKind::Array {
+            if !is_aggregate(&node.ty.clone().unwrap()) {
                 writeln!(buf, "  pop a0")?;
-                if node.ty.clone().unwrap().kind == TypeKind::Char {
-                    writeln!(buf, "  lhu a0, a0, 0")?;
-                } else {
-                    writeln!(buf, "  lw a0, a0, 0")?;
+                gen_load(buf, &node.ty.clone().unwrap())?;
+                if let NodeKind::MemAccess(n) = &node.kind {
+                    if n.member.is_bitfield() {
+                        gen_bitfield_extract(buf, &n.member)?;
+                    }
                 }
                 writeln!(buf, "  push a0")?;
             }
@@ -496,11 +788,11 @@ fn gen(buf: &mut String, node: &Node) -> Result<()> {
             gen(buf, &n.right)?;
             writeln!(buf, "  pop a1")?;
             writeln!(buf, "  pop a0")?;
-            if node.ty.clone().unwrap().kind == TypeKind::Char {
-                writeln!(buf, "  sh a1, a0, 0")?;
-            } else {
-                writeln!(buf, "  sw a1, a0, 0")?;
+            if n.left.ty.clone().unwrap().kind == TypeKind::Struct {
+                gen_memcpy(buf, &n.left.ty.clone().unwrap())?;
+                return Ok(());
             }
+            gen_store_lval(buf, &n.left)?;
             // writeln!(buf, "  push a1")?;
         }
         NodeKind::If(n) => {
@@ -583,6 +875,13 @@ fn gen(buf: &mut String, node: &Node) -> Result<()> {
             }
         }
         NodeKind::FuncCall(n) => {
+            // Variable arguments are left on the stack with the first one on top
+            for arg in n.varargs.iter().rev() {
+                gen(buf, arg)?;
+            }
+            if let Some(callee) = &n.callee {
+                gen(buf, callee)?;
+            }
             for arg in &n.args {
                 gen(buf, arg)?;
             }
@@ -590,10 +889,25 @@ fn gen(buf: &mut String, node: &Node) -> Result<()> {
                 writeln!(buf, "  pop a{}", n)?;
             }
 
-            writeln!(buf, "  lil ra, {}@l", n.name)?;
-            writeln!(buf, "  lih t0, {}@h", n.name)?;
-            writeln!(buf, "  or ra, t0")?;
+            if n.callee.is_some() {
+                writeln!(buf, "  pop ra")?;
+            } else {
+                writeln!(buf, "  lil ra, {}@l", n.name)?;
+                writeln!(buf, "  lih t0, {}@h", n.name)?;
+                writeln!(buf, "  or ra, t0")?;
+            }
             writeln!(buf, "  jalr ra, ra, 0")?;
+            if !n.varargs.is_empty() {
+                let size = n.varargs.len() * 2;
+                writeln!(buf, "  rsp t0")?;
+                if size <= 31 {
+                    writeln!(buf, "  addi t0, t0, {}", size)?;
+                } else {
+                    writeln!(buf, "  lil t1, 0x{:04x}@l", size)?;
+                    writeln!(buf, "  add t0, t1")?;
+                }
+                writeln!(buf, "  wsp t0")?;
+            }
             writeln!(buf, "  push a0")?;
         }
         NodeKind::Addr(n) => {
@@ -601,112 +915,389 @@ fn gen(buf: &mut String, node: &Node) -> Result<()> {
         }
         NodeKind::Deref(n) => {
             gen(buf, &n.unary)?;
-            if node.ty.clone().unwrap().kind != TypeKind::Array {
+            if !is_aggregate(&node.ty.clone().unwrap()) {
                 writeln!(buf, "  pop a0")?;
                 if node.ty.clone().unwrap().kind == TypeKind::Func {
                     writeln!(buf, "  jalr ra, a0, 0")?;
-                } else if node.ty.clone().unwrap().kind == TypeKind::Char {
-                    writeln!(buf, "  lhu a0, a0, 0")?;
                 } else {
-                    writeln!(buf, "  lw a0, a0, 0")?;
+                    gen_load(buf, &node.ty.clone().unwrap())?;
                 }
                 writeln!(buf, "  push a0")?;
             }
         }
-        NodeKind::PtrAdd(n) => {
-            gen(buf, &n.left)?;
-            gen(buf, &n.right)?;
-            if node.ty.clone().unwrap().ptr_to.unwrap().size <= 31 {
-                writeln!(
-                    buf,
-                    "  addi a0, zero, {}",
-                    node.ty.clone().unwrap().ptr_to.unwrap().size
-                )?;
-            } else if node.ty.clone().unwrap().ptr_to.unwrap().size <= 255 {
-                writeln!(
-                    buf,
-                    "  lil a0, 0x{:04x}@l",
-                    node.ty.clone().unwrap().ptr_to.unwrap().size
-                )?;
-            } else {
-                writeln!(
-                    buf,
-                    "  lil a0, 0x{:04x}@l",
-                    node.ty.clone().unwrap().ptr_to.unwrap().size
-                )?;
-                writeln!(
-                    buf,
-                    "  lih a1, 0x{:04x}@h",
-                    node.ty.clone().unwrap().ptr_to.unwrap().size
-                )?;
-                writeln!(buf, "  or a0, a1")?;
+        NodeKind::Cast(n) => {
+            gen(buf, &n.unary)?;
+            // Narrowing to char keeps the low byte, char is zero-extended by lhu already
+            if node.ty.clone().unwrap().kind == TypeKind::Char
+                && n.unary.ty.clone().unwrap().kind != TypeKind::Char
+            {
+                writeln!(buf, "  pop a0")?;
+                writeln!(buf, "  lil a1, 0x00ff@l")?;
+                writeln!(buf, "  and a0, a1")?;
+                writeln!(buf, "  push a0")?;
             }
-            writeln!(buf, "  push a0")?;
-            writeln!(buf, "  pop a1")?;
+        }
+        NodeKind::MemZero(n) => {
+            gen_lval(buf, &n.lval)?;
             writeln!(buf, "  pop a0")?;
-            writeln!(buf, "  mov t0, a0")?;
+            if n.size <= 31 {
+                writeln!(buf, "  addi a1, zero, {}", n.size)?;
+            } else if n.size <= 255 {
+                writeln!(buf, "  lil a1, 0x{:04x}@l", n.size)?;
+            } else {
+                writeln!(buf, "  lil a1, 0x{:04x}@l", n.size)?;
+                writeln!(buf, "  lih t0, 0x{:04x}@h", n.size)?;
+                writeln!(buf, "  or a1, t0")?;
+            }
+            writeln!(buf, "  beq a1, zero, 10")?;
+            writeln!(buf, "  sh zero, a0, 0")?;
+            writeln!(buf, "  addi a0, a0, 1")?;
             writeln!(buf, "  subi a1, a1, 1")?;
-            writeln!(buf, "  beq a1, zero, 6")?;
-            writeln!(buf, "  add a0, t0")?;
-            writeln!(buf, "  jal zero, -6")?;
-            writeln!(buf, "  push a0")?;
-            writeln!(buf, "  pop a1")?;
-            writeln!(buf, "  pop a0")?;
-            writeln!(buf, "  add a0, a1")?;
+            writeln!(buf, "  jal zero, -8")?;
+        }
+        NodeKind::PtrAdd(_) | NodeKind::PtrSub(_) if frame_addr(node).is_some() => {
+            writeln!(buf, "  mov a0, fp")?;
+            gen_add_imm(buf, frame_addr(node).unwrap())?;
             writeln!(buf, "  push a0")?;
         }
-        NodeKind::PtrSub(n) => {
-            gen(buf, &n.left)?;
-            gen(buf, &n.right)?;
-            if node.ty.clone().unwrap().ptr_to.unwrap().size <= 31 {
-                writeln!(
-                    buf,
-                    "  addi a0, zero, {}",
-                    node.ty.clone().unwrap().ptr_to.unwrap().size
-                )?;
-            } else if node.ty.clone().unwrap().ptr_to.unwrap().size <= 255 {
-                writeln!(
-                    buf,
-                    "  lil a0, 0x{:04x}@l",
-                    node.ty.clone().unwrap().ptr_to.unwrap().size
-                )?;
+        NodeKind::PtrAdd(n) | NodeKind::PtrSub(n) => {
+            let size = node.ty.clone().unwrap().ptr_to.unwrap().size;
+            if let NodeKind::Num(_) = &n.right.kind {
+                // A constant index is a fixed offset
+                let (base, offset) = split_addr(node);
+                gen(buf, base)?;
+                writeln!(buf, "  pop a0")?;
+                gen_add_imm(buf, offset)?;
             } else {
-                writeln!(
-                    buf,
-                    "  lil a0, 0x{:04x}@l",
-                    node.ty.clone().unwrap().ptr_to.unwrap().size
-                )?;
-                writeln!(
-                    buf,
-                    "  lih a1, 0x{:04x}@h",
-                    node.ty.clone().unwrap().ptr_to.unwrap().size
-                )?;
-                writeln!(buf, "  or a0, a1")?;
+                gen(buf, &n.left)?;
+                gen(buf, &n.right)?;
+                writeln!(buf, "  pop a1")?;
+                gen_scale(buf, size)?;
+                writeln!(buf, "  pop a0")?;
+                match node.kind {
+                    NodeKind::PtrAdd(_) => writeln!(buf, "  add a0, a1")?,
+                    _ => writeln!(buf, "  sub a0, a1")?,
+                }
             }
             writeln!(buf, "  push a0")?;
+        }
+
+        NodeKind::Asm(n) => {
+            for (_, input) in &n.inputs {
+                gen(buf, input)?;
+            }
+            for (reg, _) in n.inputs.iter().rev() {
+                writeln!(buf, "  pop {}", reg)?;
+            }
+            for line in asm_lines(&n.asm)? {
+                writeln!(buf, "  {}", line.trim())?;
+            }
+            for (reg, _) in &n.outputs {
+                writeln!(buf, "  push {}", reg)?;
+            }
+            for (_, output) in n.outputs.iter().rev() {
+                gen_lval(buf, output)?;
+                writeln!(buf, "  pop a0")?;
+                writeln!(buf, "  pop a1")?;
+                gen_store_lval(buf, output)?;
+            }
+        }
+        // The first variable argument is above the saved ra and fp
+        NodeKind::VaStart(n) => {
+            gen_lval(buf, &n.unary)?;
             writeln!(buf, "  pop a1")?;
-            writeln!(buf, "  pop a0")?;
-            writeln!(buf, "  mov t0, a0")?;
-            writeln!(buf, "  subi a1, a1, 1")?;
-            writeln!(buf, "  beq a1, zero, 6")?;
-            writeln!(buf, "  add a0, t0")?;
-            writeln!(buf, "  jal zero, -6")?;
-            writeln!(buf, "  push a0")?;
+            writeln!(buf, "  mov a0, fp")?;
+            writeln!(buf, "  addi a0, a0, 4")?;
+            writeln!(buf, "  sw a0, a1, 0")?;
+        }
+        // Every variable argument takes a word
+        NodeKind::VaArg(n) => {
+            gen_lval(buf, &n.unary)?;
             writeln!(buf, "  pop a1")?;
-            writeln!(buf, "  pop a0")?;
-            writeln!(buf, "  sub a0, a1")?;
+            writeln!(buf, "  lw a0, a1, 0")?;
+            writeln!(buf, "  addi t0, a0, 2")?;
+            writeln!(buf, "  sw t0, a1, 0")?;
+            gen_load(buf, &node.ty.clone().unwrap())?;
             writeln!(buf, "  push a0")?;
         }
+        // Declarations without an initializer, e.g. in a nested block
+        NodeKind::Null => {}
+    }
+    Ok(())
+}
 
-        NodeKind::Asm(n) => {
-            writeln!(buf, "  {}", n.asm)?;
+// a0 += offset. t0 and t1 are used.
+fn gen_add_imm(buf: &mut Asm, offset: i32) -> Result<()> {
+    let (op, val) = if offset < 0 {
+        ("sub", -offset as u16)
+    } else {
+        ("add", offset as u16)
+    };
+    if val == 0 {
+        return Ok(());
+    }
+    if val <= 31 {
+        writeln!(buf, "  {}i a0, a0, {}", op, val)?;
+    } else if val <= 255 {
+        writeln!(buf, "  lil t0, 0x{:04x}@l", val)?;
+        writeln!(buf, "  {} a0, t0", op)?;
+    } else {
+        writeln!(buf, "  lil t0, 0x{:04x}@l", val)?;
+        writeln!(buf, "  lih t1, 0x{:04x}@h", val)?;
+        writeln!(buf, "  or t0, t1")?;
+        writeln!(buf, "  {} a0, t0", op)?;
+    }
+    Ok(())
+}
+
+// a1 *= size by shifts and adds. t0 is used.
+fn gen_scale(buf: &mut Asm, size: u16) -> Result<()> {
+    if size.is_power_of_two() {
+        let shift = size.trailing_zeros();
+        if shift > 0 {
+            writeln!(buf, "  addi t0, zero, {}", shift)?;
+            writeln!(buf, "  sll a1, t0")?;
+        }
+        return Ok(());
+    }
+    // Sum a1 << k for every bit k of size
+    writeln!(buf, "  addi t0, zero, 0")?;
+    for k in 0..16 - size.leading_zeros() {
+        if size & (1 << k) != 0 {
+            writeln!(buf, "  add t0, a1")?;
+        }
+        if size >> (k + 1) != 0 {
+            writeln!(buf, "  add a1, a1")?;
+        }
+    }
+    writeln!(buf, "  mov a1, t0")?;
+    Ok(())
+}
+
+// Offset from fp of a local lvalue at a fixed place in the frame
+fn frame_offset(node: &Node) -> Option<i32> {
+    match &node.kind {
+        NodeKind::Var(n) if !n.is_global => Some(-(n.offset as i32)),
+        NodeKind::MemAccess(n) => Some(frame_offset(&n.unary)? + n.member.offset as i32),
+        NodeKind::Deref(n) => frame_addr(&n.unary),
+        _ => None,
+    }
+}
+
+// Offset from fp of an address value, e.g. of a local array with a constant index
+fn frame_addr(node: &Node) -> Option<i32> {
+    match &node.kind {
+        NodeKind::Var(_) if node.ty.clone().unwrap().kind == TypeKind::Array => frame_offset(node),
+        NodeKind::Addr(n) => frame_offset(&n.unary),
+        NodeKind::PtrAdd(n) | NodeKind::PtrSub(n) => {
+            let NodeKind::Num(index) = &n.right.kind else {
+                return None;
+            };
+            let offset = index.val * node.ty.clone().unwrap().ptr_to.unwrap().size as i32;
+            match node.kind {
+                NodeKind::PtrAdd(_) => Some(frame_addr(&n.left)? + offset),
+                _ => Some(frame_addr(&n.left)? - offset),
+            }
+        }
+        _ => None,
+    }
+}
+
+// An lvalue as the address value of another node plus a constant offset
+fn split_lval(node: &Node) -> Option<(&Node, i32)> {
+    match &node.kind {
+        NodeKind::Deref(n) => Some(split_addr(&n.unary)),
+        NodeKind::MemAccess(n) => {
+            let (base, offset) = split_lval(&n.unary)?;
+            Some((base, offset + n.member.offset as i32))
+        }
+        _ => None,
+    }
+}
+
+// An address value as another one plus a constant offset, e.g. p + 2
+fn split_addr(node: &Node) -> (&Node, i32) {
+    match &node.kind {
+        NodeKind::PtrAdd(n) | NodeKind::PtrSub(n) => {
+            let NodeKind::Num(index) = &n.right.kind else {
+                return (node, 0);
+            };
+            let mut offset = index.val * node.ty.clone().unwrap().ptr_to.unwrap().size as i32;
+            if let NodeKind::PtrSub(_) = node.kind {
+                offset = -offset;
+            }
+            let (base, base_offset) = split_addr(&n.left);
+            (base, base_offset + offset)
         }
-        NodeKind::Null => unreachable!(),
+        NodeKind::Addr(n) => split_lval(&n.unary).unwrap_or((node, 0)),
+        _ => (node, 0),
     }
+}
+
+fn is_aggregate(ty: &Type) -> bool {
+    ty.kind == TypeKind::Array || ty.kind == TypeKind::Struct || ty.is_function()
+}
+
+// Relational comparison of unsigned operands. Addresses are unsigned too.
+fn is_unsigned_cmp(n: &Binary) -> bool {
+    let is_unsigned = |node: &Node| {
+        let ty = node.ty.clone().unwrap();
+        ty.is_uint() || ty.is_pointer()
+    };
+    is_unsigned(&n.left) || is_unsigned(&n.right)
+}
+
+// Unsigned division by shift and subtract
+// a0 = a0 / a1, t0 = a0 % a1
+fn gen_udivmod(buf: &mut Asm) -> Result<()> {
+    // A divisor with the top bit set gives a quotient of 0 or 1
+    writeln!(buf, "  bge a1, zero, 14")?;
+    writeln!(buf, "  mov t0, a0")?;
+    writeln!(buf, "  mov a0, zero")?;
+    writeln!(buf, "  bltu t0, a1, 30")?;
+    writeln!(buf, "  sub t0, a1")?;
+    writeln!(buf, "  addi a0, zero, 1")?;
+    writeln!(buf, "  jal zero, 24")?;
+
+    // Shift the dividend into the remainder and the quotient into the dividend
+    writeln!(buf, "  mov t0, zero")?;
+    writeln!(buf, "  addi a2, zero, 16")?;
+    writeln!(buf, "  add t0, t0")?;
+    writeln!(buf, "  bge a0, zero, 4")?;
+    writeln!(buf, "  addi t0, t0, 1")?;
+    writeln!(buf, "  add a0, a0")?;
+    writeln!(buf, "  bltu t0, a1, 6")?;
+    writeln!(buf, "  sub t0, a1")?;
+    writeln!(buf, "  addi a0, a0, 1")?;
+    writeln!(buf, "  subi a2, a2, 1")?;
+    writeln!(buf, "  bnq a2, zero, -16")?;
+    Ok(())
+}
+
+// Copy a value of type ty from the address in a1 to the address in a0,
+// word by word unless it may be unaligned
+fn gen_memcpy(buf: &mut Asm, ty: &Type) -> Result<()> {
+    let (load, store, unit) = if ty.align >= 2 {
+        ("lw", "sw", 2)
+    } else {
+        ("lhu", "sh", 1)
+    };
+    let count = ty.size / unit;
+    if count <= 31 {
+        writeln!(buf, "  addi a2, zero, {}", count)?;
+    } else if count <= 255 {
+        writeln!(buf, "  lil a2, 0x{:04x}@l", count)?;
+    } else {
+        writeln!(buf, "  lil a2, 0x{:04x}@l", count)?;
+        writeln!(buf, "  lih t0, 0x{:04x}@h", count)?;
+        writeln!(buf, "  or a2, t0")?;
+    }
+    writeln!(buf, "  beq a2, zero, 14")?;
+    writeln!(buf, "  {} t0, a1, 0", load)?;
+    writeln!(buf, "  {} t0, a0, 0", store)?;
+    writeln!(buf, "  addi a0, a0, {}", unit)?;
+    writeln!(buf, "  addi a1, a1, {}", unit)?;
+    writeln!(buf, "  subi a2, a2, 1")?;
+    writeln!(buf, "  jal zero, -12")?;
     Ok(())
 }
 
-fn gen_lval(buf: &mut String, node: &Node) -> Result<()> {
+// Load a value of type ty from the address in a0 into a0
+fn gen_load(buf: &mut Asm, ty: &Type) -> Result<()> {
+    if ty.size == 1 {
+        writeln!(buf, "  lhu a0, a0, 0")?;
+    } else if ty.align == 1 {
+        // Unaligned word, e.g. a member of a packed struct
+        writeln!(buf, "  lhu a1, a0, 1")?;
+        writeln!(buf, "  lhu a0, a0, 0")?;
+        writeln!(buf, "  addi t0, zero, 8")?;
+        writeln!(buf, "  sll a1, t0")?;
+        writeln!(buf, "  or a0, a1")?;
+    } else {
+        writeln!(buf, "  lw a0, a0, 0")?;
+    }
+    Ok(())
+}
+
+// Store a1 as a value of type ty to the address in a0
+fn gen_store(buf: &mut Asm, ty: &Type) -> Result<()> {
+    if ty.size == 1 {
+        writeln!(buf, "  sh a1, a0, 0")?;
+    } else if ty.align == 1 {
+        writeln!(buf, "  sh a1, a0, 0")?;
+        writeln!(buf, "  addi t0, zero, 8")?;
+        writeln!(buf, "  srl a1, t0")?;
+        writeln!(buf, "  sh a1, a0, 1")?;
+    } else {
+        writeln!(buf, "  sw a1, a0, 0")?;
+    }
+    Ok(())
+}
+
+// Store a1 to the lvalue node, whose address is in a0
+fn gen_store_lval(buf: &mut Asm, node: &Node) -> Result<()> {
+    if let NodeKind::MemAccess(m) = &node.kind {
+        if m.member.is_bitfield() {
+            gen_bitfield_insert(buf, &m.member)?;
+        }
+    }
+    gen_store(buf, &node.ty.clone().unwrap())
+}
+
+// Extract the bit-field member from its storage unit in a0. A signed int
+// bit-field is sign-extended and any other one is zero-extended.
+fn gen_bitfield_extract(buf: &mut Asm, member: &Member) -> Result<()> {
+    if member.ty.kind == TypeKind::Int && !member.ty.is_unsigned {
+        writeln!(
+            buf,
+            "  addi t0, zero, {}",
+            16 - member.bit_offset - member.bit_width
+        )?;
+        writeln!(buf, "  sll a0, t0")?;
+        writeln!(buf, "  addi t0, zero, {}", 16 - member.bit_width)?;
+        writeln!(buf, "  sra a0, t0")?;
+    } else {
+        writeln!(buf, "  addi t0, zero, {}", member.bit_offset)?;
+        writeln!(buf, "  srl a0, t0")?;
+        gen_bitfield_mask(buf, member)?;
+        writeln!(buf, "  and a0, t0")?;
+    }
+    Ok(())
+}
+
+// Merge the value in a1 into the storage unit at the address in a0 and leave
+// the new unit in a1, keeping the other bits of the unit
+fn gen_bitfield_insert(buf: &mut Asm, member: &Member) -> Result<()> {
+    writeln!(buf, "  push a0")?;
+    writeln!(buf, "  push a1")?;
+    gen_load(buf, &member.ty)?;
+    writeln!(buf, "  pop a1")?;
+    gen_bitfield_mask(buf, member)?;
+    writeln!(buf, "  and a1, t0")?;
+    writeln!(buf, "  addi t1, zero, {}", member.bit_offset)?;
+    writeln!(buf, "  sll a1, t1")?;
+    writeln!(buf, "  sll t0, t1")?;
+    // Clear the bits of the member
+    writeln!(buf, "  or a0, t0")?;
+    writeln!(buf, "  xor a0, t0")?;
+    writeln!(buf, "  or a0, a1")?;
+    writeln!(buf, "  mov a1, a0")?;
+    writeln!(buf, "  pop a0")?;
+    Ok(())
+}
+
+// Load the mask of the width of the bit-field member into t0
+fn gen_bitfield_mask(buf: &mut Asm, member: &Member) -> Result<()> {
+    let mask = ((1u32 << member.bit_width) - 1) as u16;
+    writeln!(buf, "  lil t0, 0x{:04x}@l", mask)?;
+    if mask > 255 {
+        writeln!(buf, "  lih t1, 0x{:04x}@h", mask)?;
+        writeln!(buf, "  or t0, t1")?;
+    }
+    Ok(())
+}
+
+fn gen_lval(buf: &mut Asm, node: &Node) -> Result<()> {
     match &node.kind {
         NodeKind::Var(n) => {
             if n.is_global {
@@ -716,40 +1307,34 @@ fn gen_lval(buf: &mut String, node: &Node) -> Result<()> {
                 writeln!(buf, "  push a0")?;
             } else {
                 writeln!(buf, "  mov a0, fp")?;
-                if n.offset <= 31 {
-                    writeln!(buf, "  subi a0, a0, {}", n.offset)?;
-                } else if n.offset <= 255 {
-                    writeln!(buf, "  lil t0, 0x{:04x}@l", n.offset)?;
-                    writeln!(buf, "  sub a0, t0")?;
-                } else {
-                    writeln!(buf, "  lil t0, 0x{:04x}@l", n.offset)?;
-                    writeln!(buf, "  lih t1, 0x{:04x}@h", n.offset)?;
-                    writeln!(buf, "  or t0, t1")?;
-                    writeln!(buf, "  sub a0, t0")?;
-                }
+                gen_add_imm(buf, -(n.offset as i32))?;
                 writeln!(buf, "  push a0")?;
             }
         }
-        NodeKind::Deref(n) => {
-            gen(buf, &n.unary)?;
+        // e.g. arr[2].x of a local array, at a fixed offset from fp
+        NodeKind::Deref(_) | NodeKind::MemAccess(_) if frame_offset(node).is_some() => {
+            writeln!(buf, "  mov a0, fp")?;
+            gen_add_imm(buf, frame_offset(node).unwrap())?;
+            writeln!(buf, "  push a0")?;
+        }
+        // e.g. p[2].x, at a fixed offset from the value of p
+        NodeKind::Deref(_) | NodeKind::MemAccess(_) if split_lval(node).is_some() => {
+            let (base, offset) = split_lval(node).unwrap();
+            gen(buf, base)?;
+            writeln!(buf, "  pop a0")?;
+            gen_add_imm(buf, offset)?;
+            writeln!(buf, "  push a0")?;
         }
         NodeKind::MemAccess(n) => {
             gen_lval(buf, &n.unary)?;
             writeln!(buf, "  pop a0")?;
-            if n.member.offset <= 31 {
-                writeln!(buf, "  addi a0, a0, {}", n.member.offset)?;
-            } else if n.member.offset <= 255 {
-                writeln!(buf, "  lil t0, 0x{:04x}@l", n.member.offset)?;
-                writeln!(buf, "  or t0, t1")?;
-                writeln!(buf, "  add a0, t0")?;
-            } else {
-                writeln!(buf, "  lil t0, 0x{:04x}@l", n.member.offset)?;
-                writeln!(buf, "  lih t1, 0x{:04x}@h", n.member.offset)?;
-                writeln!(buf, "  or t0, t1")?;
-                writeln!(buf, "  add a0, t0")?;
-            }
+            gen_add_imm(buf, n.member.offset as i32)?;
             writeln!(buf, "  push a0")?;
         }
+        // A returned struct is addressed in the temporary of the caller
+        NodeKind::FuncCall(_) if node.ty.clone().unwrap().kind == TypeKind::Struct => {
+            gen(buf, node)?;
+        }
         NodeKind::FuncCall(n) => {
             writeln!(buf, "  lil a0, {}@l", n.name)?;
             writeln!(buf, "  lih a1, {}@h", n.name)?;
@@ -1,3 +1,5 @@
+#![allow(static_mut_refs)]
+
 use std::{
     collections::HashMap,
     io::{BufReader, Read, Write},
@@ -6,7 +8,7 @@ use std::{
 use anyhow::{anyhow, Context, Result};
 use codegen::codegen;
 use nom::error::convert_error;
-use parse::{parse_define, parse_program, Program};
+use parse::{parse_define, parse_program, take_warnings, Program, WarningKind};
 
 mod codegen;
 mod parse;
@@ -23,6 +25,10 @@ struct Args {
     /// output file name
     #[arg(short = 'o', default_value = "a.asm")]
     output_file_name: std::path::PathBuf,
+
+    /// warn when a local declaration shadows another one
+    #[arg(long = "Wshadow")]
+    warn_shadow: bool,
 }
 
 fn main() -> Result<()> {
@@ -47,6 +53,23 @@ fn main() -> Result<()> {
                 let ast = parse_program(&res, program.globals.clone());
                 match ast {
                     Ok((_, (mut funcs, globals))) => {
+                        for warning in take_warnings() {
+                            let enabled = match warning.kind {
+                                WarningKind::Shadow => args.warn_shadow,
+                                WarningKind::DiscardedQualifiers => true,
+                                WarningKind::ImplicitDeclaration => true,
+                                WarningKind::PointerTypes => true,
+                            };
+                            if enabled {
+                                let line = res[..res.len() - warning.pos].matches('\n').count() + 1;
+                                eprintln!(
+                                    "{}:{}: warning: {}",
+                                    &file_path.display(),
+                                    line,
+                                    warning.msg
+                                );
+                            }
+                        }
                         program.funcs.append(&mut funcs);
                         program.globals = globals;
                         defines = d;
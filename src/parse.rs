@@ -1,20 +1,24 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use nom::{
     branch::{alt, permutation},
-    bytes::complete::{tag, take_until, take_while1},
+    bytes::complete::{tag, take_until, take_while, take_while1},
     character::{
-        complete::{alphanumeric1, digit1, hex_digit1, multispace0, multispace1},
+        complete::{alphanumeric1, digit1, multispace0, multispace1},
         is_alphanumeric,
     },
-    combinator::{fail, opt},
+    combinator::{fail, opt, peek, verify},
     error::{context, VerboseError},
-    multi::{many0, many0_count, separated_list0},
-    sequence::delimited,
+    multi::{many0, separated_list0},
+    sequence::{delimited, preceded, terminated},
     IResult,
 };
 
-use crate::ty::{create_char_type, create_func_type, create_int_type, Member, Type, TypeKind};
+use crate::ty::{
+    align_to, create_array_type, create_char_type, create_func_type, create_function_type,
+    create_incomplete_struct_type, create_int_type, create_pointer_type, create_uint_type, Member,
+    Type, TypeKind,
+};
 
 // AST node
 #[derive(Debug, PartialEq, Clone)]
@@ -55,19 +59,24 @@ pub enum NodeKind {
     Block(Block),         // {...}
     FuncCall(FuncCall),   // Functon call
     MemAccess(MemAccess), // . Struct member access
+    Neg(Unary),           // unary -
     Addr(Unary),          // unary &
     Deref(Unary),         // unary *
+    Cast(Unary),          // (type)
+    MemZero(MemZero),     // Zero-fill of a variable
     PtrAdd(Binary),       //
     PtrSub(Binary),       //
     Num(Num),             // Integer
     Var(Var),             // Variable
     Asm(Asm),             // Assembler
+    VaStart(Unary),       // va_start
+    VaArg(Unary),         // va_arg
     Null,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Num {
-    pub val: u16,
+    pub val: i32,
 }
 #[derive(Debug, PartialEq, Clone)]
 pub struct Binary {
@@ -137,23 +146,15 @@ pub struct Block {
 #[derive(Debug, PartialEq, Clone)]
 pub struct FuncCall {
     pub name: String,
+    pub callee: Option<Box<Node>>, // Address of the function of an indirect call
     pub args: Vec<Node>,
+    pub varargs: Vec<Node>, // Arguments passed on the stack to a variadic function
 }
 
 #[derive(Debug, PartialEq, Clone)]
-pub struct Addr {
-    pub unary: Box<Node>,
-}
-
-#[derive(Debug, PartialEq, Clone)]
-pub struct Deref {
-    pub unary: Box<Node>,
-}
-
-#[derive(Debug, PartialEq, Clone)]
-pub struct PtrBinary {
-    pub left: Box<Node>,
-    pub right: Box<Node>,
+pub struct MemZero {
+    pub lval: Box<Node>,
+    pub size: u16,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -164,7 +165,9 @@ pub struct MemAccess {
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Asm {
-    pub asm: String,
+    pub asm: String,                  // Operands replaced by their registers or values
+    pub inputs: Vec<(String, Node)>,  // Registers loaded with the values before the asm
+    pub outputs: Vec<(String, Node)>, // Registers stored to the lvalues after the asm
 }
 
 // Entire input program
@@ -178,12 +181,13 @@ pub struct Program {
 #[derive(Debug)]
 pub struct Func {
     pub kind: FuncKind,
+    #[allow(dead_code)]
     pub ty: Box<Type>,
     pub name: String,
     pub args: Vec<Node>,
     pub nodes: Vec<Node>,
     pub local_offset: u16,
-    pub is_naked: bool,
+    pub attrs: Attrs,
 }
 
 #[derive(Debug, PartialEq)]
@@ -191,6 +195,35 @@ pub enum FuncKind {
     Init,
     Main,
     Other,
+    Asm, // File-scope asm, emitted in place among the other functions
+}
+
+// Attributes of a declaration given by __attribute__((...)). noreturn, used
+// and interrupt are recorded but don't change the generated code.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Attrs {
+    pub is_naked: bool,          // No prologue, the body sets up the frame itself
+    pub section: Option<String>, // Emitted after a label of this name
+    pub aligned: Option<u16>,    // Least alignment of a struct, a variable or a function
+    pub is_noreturn: bool,
+    pub is_used: bool,
+    pub is_interrupt: bool,
+    pub is_packed: bool, // Struct members without padding
+}
+
+impl Attrs {
+    // These attributes together with the ones of another declaration
+    fn merge(self, other: Attrs) -> Attrs {
+        Attrs {
+            is_naked: self.is_naked || other.is_naked,
+            section: self.section.or(other.section),
+            aligned: self.aligned.max(other.aligned),
+            is_noreturn: self.is_noreturn || other.is_noreturn,
+            is_used: self.is_used || other.is_used,
+            is_interrupt: self.is_interrupt || other.is_interrupt,
+            is_packed: self.is_packed || other.is_packed,
+        }
+    }
 }
 
 // Scope
@@ -201,14 +234,35 @@ pub struct Scope {
     pub name: String,
     pub str: Option<String>,
     pub offset: Option<u16>,
+    pub init: Option<Vec<InitData>>,
+    pub storage: Storage,
+    pub attrs: Attrs,
+    pub is_defined: bool, // A function whose body has been parsed
+}
+
+// Storage class of a declaration
+#[derive(Debug, PartialEq, Clone)]
+pub enum Storage {
+    Default,
+    Static(String), // File-local or static local, with its unique label
+    Extern,         // Declared only, defined in another file or module
+}
+
+// Initial value of a global variable
+#[derive(Debug, PartialEq, Clone)]
+pub enum InitData {
+    Byte(u8),
+    Label(String, u16), // Address of label + offset (2 bytes)
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum ScopeKind {
     Var,     // Local variable, global variable
     Str,     // String
-    Tag,     // Struct tag
+    Tag,     // Struct or enum tag
     Typedef, // Typedef
+    Func,    // Function
+    Enum,    // Enumeration constant, whose value is kept in offset
     Null,
 }
 
@@ -242,8 +296,45 @@ static mut GLOBALS: Vec<Scope> = Vec::new();
 static mut LOCALS: Vec<Scope> = Vec::new();
 static mut IS_GLOBAL: bool = false;
 static mut STR_LABEL_COUNT: u16 = 0;
+static mut STATIC_LABEL_COUNT: u16 = 0;
 static mut CTR_LABEL_COUNT: u16 = 0;
 static mut SCOPE_CTR_LABEL: u16 = 0;
+static mut WARNINGS: Vec<Warning> = Vec::new();
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum WarningKind {
+    Shadow,              // A local declaration hides an outer one
+    DiscardedQualifiers, // A pointer assignment drops const or volatile
+    ImplicitDeclaration, // A function is called before it is declared
+    PointerTypes,        // Pointers of different types or a pointer and an integer are mixed
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Warning {
+    pub kind: WarningKind,
+    pub pos: usize, // Length of the remaining text at the warning
+    pub msg: String,
+}
+
+fn warn(text: &str, kind: WarningKind, msg: String) {
+    unsafe {
+        let pos = text.len();
+        if !WARNINGS.iter().any(|w| w.pos == pos && w.kind == kind) {
+            WARNINGS.push(Warning { kind, pos, msg });
+        }
+    }
+}
+
+// An error message that names something of the source. Compilation stops at
+// the first error, so the message is simply kept for the rest of the run.
+fn error_msg(msg: String) -> &'static str {
+    Box::leak(msg.into_boxed_str())
+}
+
+// Warnings collected since the last call
+pub fn take_warnings() -> Vec<Warning> {
+    unsafe { std::mem::take(&mut WARNINGS) }
+}
 
 pub fn parse_program(
     mut text: &str,
@@ -257,21 +348,143 @@ pub fn parse_program(
         if i.is_empty() {
             break;
         }
-        let (i, global) = opt(parse_global)(i)?;
-        if let Some(global) = global {
-            unsafe {
-                GLOBALS.push(global);
-            }
-            text = i;
-        } else {
-            let (i, func) = parse_function(i)?;
+        if let (i, Some(_)) = opt(tag("__asm__"))(i)? {
+            let (i, func) = parse_file_asm(i)?;
             funcs.push(func);
             text = i;
+            continue;
+        }
+        let len = unsafe { GLOBALS.len() };
+        match parse_global(i) {
+            Ok((i, global)) => {
+                declare_global(global);
+                text = i;
+            }
+            Err(nom::Err::Error(global_err)) => {
+                // Forget what the failed attempt declared, e.g. the tag of the return type
+                unsafe { GLOBALS.truncate(len) };
+                match parse_function(i) {
+                    Ok((i, func)) => {
+                        funcs.push(func);
+                        text = i;
+                    }
+                    // Report the error of whichever parse got further
+                    Err(nom::Err::Error(func_err)) => {
+                        let rest =
+                            |e: &VerboseError<&str>| e.errors.first().map_or(0, |e| e.0.len());
+                        if rest(&global_err) <= rest(&func_err) {
+                            return Err(nom::Err::Error(global_err));
+                        }
+                        return Err(nom::Err::Error(func_err));
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    unsafe {
+        // File-local names are not visible from the other files
+        for global in GLOBALS.iter_mut() {
+            if let Storage::Static(label) = &global.storage {
+                global.name = label.clone();
+            }
         }
     }
     Ok((text, (funcs, unsafe { GLOBALS.clone() })))
 }
 
+// File-scope asm, which is emitted as it is between the functions. It can't
+// have operands since there are no registers to hand out outside a function.
+fn parse_file_asm(text: &str) -> IResult<&str, Func, VerboseError<&str>> {
+    let (i, node) = parse_asm(text)?;
+    if let NodeKind::Asm(asm) = &node.kind {
+        if !asm.inputs.is_empty() || !asm.outputs.is_empty() {
+            return context("Operands in file-scope asm", fail)(text);
+        }
+    }
+    Ok((
+        i,
+        Func {
+            kind: FuncKind::Asm,
+            ty: Box::new(create_function_type(create_func_type(), vec![], false)),
+            name: "".to_string(),
+            args: vec![],
+            nodes: vec![node],
+            local_offset: 0,
+            attrs: Attrs::default(),
+        },
+    ))
+}
+
+// Add a global, merging extern declarations and prototypes with the definition
+fn declare_global(global: Scope) {
+    unsafe {
+        let prev = GLOBALS
+            .iter()
+            .position(|g| g.kind == global.kind && g.name == global.name);
+        match prev {
+            Some(_) if global.storage == Storage::Extern => {}
+            Some(idx)
+                if GLOBALS[idx].storage == Storage::Extern || global.kind == ScopeKind::Func =>
+            {
+                let attrs = global.attrs.merge(GLOBALS[idx].attrs.clone());
+                GLOBALS[idx] = Scope { attrs, ..global }
+            }
+            _ => GLOBALS.push(global),
+        }
+    }
+}
+
+// Check a global variable declaration against an earlier one of the same name
+fn check_global<'a>(
+    text: &'a str,
+    ident: &str,
+    ty: &Type,
+    storage: &Storage,
+) -> IResult<&'a str, (), VerboseError<&'a str>> {
+    // A file-local variable is a new symbol
+    if let Storage::Static(_) = storage {
+        return Ok((text, ()));
+    }
+    unsafe {
+        if let Some(prev) = GLOBALS
+            .iter()
+            .find(|g| g.kind == ScopeKind::Var && g.name == ident)
+        {
+            if !prev.ty.clone().unwrap().is_compatible(ty) {
+                return context("Conflicting types", fail)(text);
+            }
+            if prev.storage != Storage::Extern && *storage != Storage::Extern {
+                return context("Redefinition of global variable", fail)(text);
+            }
+        }
+    }
+    Ok((text, ()))
+}
+
+// storage_class = ("static" | "extern")?
+fn parse_storage(text: &str) -> IResult<&str, Option<&str>, VerboseError<&str>> {
+    let (i, storage) = opt(verify(take_while1(is_ident), |s: &str| {
+        matches!(s, "static" | "extern")
+    }))(text)?;
+    let (i, _) = multispace0(i)?;
+    Ok((i, storage))
+}
+
+// Storage of a declaration. Statics get a label unique to the whole program.
+fn new_storage(storage: Option<&str>, ident: &str) -> Storage {
+    match storage {
+        Some("static") => unsafe {
+            let label = format!("__static{}_{}", STATIC_LABEL_COUNT, ident);
+            STATIC_LABEL_COUNT += 1;
+            Storage::Static(label)
+        },
+        Some("extern") => Storage::Extern,
+        _ => Storage::Default,
+    }
+}
+
 fn parse_space_or_comment(text: &str) -> IResult<&str, &str, VerboseError<&str>> {
     let (mut i, _) = multispace0(text)?;
     loop {
@@ -308,27 +521,74 @@ fn is_ident(c: char) -> bool {
 
 // declspec = "int" | "char" | "func" | struct-decl | "typedef" declspec ident | ident
 fn parse_declspec(text: &str) -> IResult<&str, Type, VerboseError<&str>> {
-    let (i, type_kind) = opt(alt((
-        tag("int"),
-        tag("char"),
-        tag("func"),
-        tag("struct"),
-        tag("typedef"),
-    )))(text)?;
+    let (i, pre) = parse_qualifiers(text)?;
+    let (i, ty) = parse_declspec_base(i)?;
+    let (i, _) = multispace0(i)?;
+    let (i, post) = parse_qualifiers(i)?;
+    Ok((i, ty.qualified_by(&pre.qualified_by(&post))))
+}
+
+// qualifiers = ("const" | "volatile")*
+// Returns an int type which only carries the qualifiers.
+fn parse_qualifiers(text: &str) -> IResult<&str, Type, VerboseError<&str>> {
+    let (i, quals) = many0(terminated(
+        verify(take_while1(is_ident), |s: &str| {
+            matches!(s, "const" | "volatile")
+        }),
+        multispace0,
+    ))(text)?;
+    let mut ty = create_int_type();
+    ty.is_const = quals.contains(&"const");
+    ty.is_volatile = quals.contains(&"volatile");
+    Ok((i, ty))
+}
+
+fn parse_declspec_base(text: &str) -> IResult<&str, Type, VerboseError<&str>> {
+    // "short" and "long" are as wide as "int"
+    let (i, modifiers) = many0(terminated(
+        verify(take_while1(is_ident), |s: &str| {
+            matches!(s, "unsigned" | "signed" | "short" | "long")
+        }),
+        multispace0,
+    ))(text)?;
+    if !modifiers.is_empty() {
+        let (i, kind) = opt(verify(take_while1(is_ident), |s: &str| {
+            s == "int" || s == "char"
+        }))(i)?;
+        let mut ty = match kind {
+            Some("char") => create_char_type(),
+            _ => create_int_type(),
+        };
+        ty.is_unsigned = modifiers.contains(&"unsigned");
+        return Ok((i, ty));
+    }
+
+    let (i, type_kind) = opt(verify(take_while1(is_ident), |s: &str| {
+        matches!(
+            s,
+            "int" | "char" | "func" | "va_list" | "struct" | "union" | "enum" | "typedef"
+        )
+    }))(text)?;
 
     if let Some(type_kind) = type_kind {
         match type_kind {
             "int" => Ok((i, create_int_type())),
             "char" => Ok((i, create_char_type())),
             "func" => Ok((i, create_func_type())),
-            "struct" => {
+            // Pointer to the next variable argument
+            "va_list" => Ok((i, create_pointer_type(create_char_type()))),
+            "struct" | "union" => {
+                let (i, _) = multispace0(i)?;
+                Ok(parse_struct_decl(i, type_kind == "union")?)
+            }
+            "enum" => {
                 let (i, _) = multispace0(i)?;
-                Ok(parse_struct_decl(i)?)
+                Ok(parse_enum_decl(i)?)
             }
             "typedef" => {
                 let (i, _) = multispace1(i)?;
                 let (i, ty) = parse_declspec(i)?;
-                let (i, _) = multispace1(i)?;
+                let (i, _) = multispace0(i)?;
                 let (i, (ident, ty)) = parse_declarator(i, ty)?;
                 unsafe {
                     if IS_GLOBAL {
@@ -338,14 +598,23 @@ fn parse_declspec(text: &str) -> IResult<&str, Type, VerboseError<&str>> {
                             name: ident.to_string(),
                             str: None,
                             offset: None,
+                            init: None,
+                            storage: Storage::Default,
+                            attrs: Attrs::default(),
+                            is_defined: false,
                         })
                     } else {
+                        check_local(i, ident, ScopeKind::Typedef)?;
                         LOCALS.push(Scope {
                             kind: ScopeKind::Typedef,
                             ty: Some(Box::new(ty.clone())),
                             name: ident.to_string(),
                             str: None,
                             offset: None,
+                            init: None,
+                            storage: Storage::Default,
+                            attrs: Attrs::default(),
+                            is_defined: false,
                         })
                     }
                 }
@@ -358,51 +627,91 @@ fn parse_declspec(text: &str) -> IResult<&str, Type, VerboseError<&str>> {
         }
     } else {
         let (i, ident) = take_while1(is_ident)(i)?;
-        unsafe {
-            if let Some(typedef) = LOCALS
-                .iter()
-                .find(|&scope| scope.kind == ScopeKind::Typedef && scope.name == ident)
-            {
-                Ok((i, *typedef.ty.clone().unwrap()))
-            } else if let Some(gtypedef) = GLOBALS
+        if let Some(ty) = find_typedef(ident) {
+            // The struct may have been defined after the typedef
+            Ok((i, complete_type(ty)))
+        } else {
+            context("Unknown type", fail)(i)
+        }
+    }
+}
+
+// A local variable declared later hides a typedef of the same name
+fn find_typedef(ident: &str) -> Option<Type> {
+    unsafe {
+        if let Some(local) = LOCALS.iter().rev().find(|&scope| {
+            (scope.kind == ScopeKind::Typedef || scope.kind == ScopeKind::Var)
+                && scope.name == ident
+        }) {
+            (local.kind == ScopeKind::Typedef).then(|| *local.ty.clone().unwrap())
+        } else {
+            GLOBALS
                 .iter()
                 .find(|&scope| scope.kind == ScopeKind::Typedef && scope.name == ident)
-            {
-                return Ok((i, *gtypedef.ty.clone().unwrap()));
-            } else {
-                return context("Unknown type", fail)(i);
-            }
+                .map(|gtypedef| *gtypedef.ty.clone().unwrap())
         }
     }
 }
 
+// Whether text starts with a type name rather than an expression
+fn is_typename(text: &str) -> bool {
+    let ident: IResult<&str, &str, VerboseError<&str>> = take_while1(is_ident)(text);
+    match ident {
+        Ok((
+            _,
+            "int" | "char" | "func" | "va_list" | "struct" | "union" | "enum" | "typedef"
+            | "static" | "extern" | "unsigned" | "signed" | "short" | "long" | "const" | "volatile"
+            | "__attribute__",
+        )) => true,
+        Ok((_, ident)) => find_typedef(ident).is_some(),
+        Err(_) => false,
+    }
+}
+
+// type_name = declspec abstract_declarator
+fn parse_typename(text: &str) -> IResult<&str, Type, VerboseError<&str>> {
+    let (i, ty) = parse_declspec(text)?;
+    let (i, _) = multispace0(i)?;
+    parse_abstract_declarator(i, ty)
+}
+
 // struct_decl = ident? "{" (struct_member)* "}"
-fn parse_struct_decl(text: &str) -> IResult<&str, Type, VerboseError<&str>> {
+fn parse_struct_decl(text: &str, is_union: bool) -> IResult<&str, Type, VerboseError<&str>> {
     let mut members = Vec::new();
     let (i, _) = multispace0(text)?;
+    let (i, mut attrs) = parse_attributes(i)?;
     let (i, ident) = opt(take_while1(is_ident))(i)?;
     let (i, _) = multispace0(i)?;
     let (mut t, bracket) = opt(tag("{"))(i)?;
     if let Some(ident) = ident {
         if bracket.is_none() {
-            unsafe {
-                if let Some(tag) = LOCALS
-                    .iter()
-                    .find(|&scope| scope.kind == ScopeKind::Tag && scope.name == ident)
-                {
-                    return Ok((i, *tag.ty.clone().unwrap()));
-                } else if let Some(gtag) = GLOBALS
-                    .iter()
-                    .find(|&scope| scope.kind == ScopeKind::Tag && scope.name == ident)
-                {
-                    return Ok((i, *gtag.ty.clone().unwrap()));
-                } else {
-                    return context("Unknown struct type", fail)(i);
-                }
+            // An unknown tag declares an incomplete struct, defined later
+            if let Some(ty) = lookup_tag(ident) {
+                let (i, _) = check_tag_kind(i, ident, &ty, is_union)?;
+                return Ok((i, ty));
             }
+            let ty = create_incomplete_struct_type(ident, is_union);
+            declare_tag(i, ident, ty.clone())?;
+            return Ok((i, ty));
+        }
+        // The members may point to the struct itself, e.g. struct node *next
+        let prev = unsafe {
+            let scopes = if IS_GLOBAL {
+                &GLOBALS[..]
+            } else {
+                &LOCALS[BLOCK_START..]
+            };
+            scopes
+                .iter()
+                .find(|scope| scope.kind == ScopeKind::Tag && scope.name == ident)
+                .map(|scope| *scope.ty.clone().unwrap())
+        };
+        if let Some(prev) = prev {
+            check_tag_kind(t, ident, &prev, is_union)?;
+        } else {
+            declare_tag(t, ident, create_incomplete_struct_type(ident, is_union))?;
         }
     }
-    let mut offset = 0;
     loop {
         let (i, _) = multispace0(t)?;
         let (i, s) = opt(tag("}"))(i)?;
@@ -413,89 +722,661 @@ fn parse_struct_decl(text: &str) -> IResult<&str, Type, VerboseError<&str>> {
         let (i, _) = multispace0(i)?;
         let (i, ty) = parse_declspec(i)?;
         let (i, _) = multispace0(i)?;
-        let (i, (ident, mem_ty)) = parse_declarator(i, ty)?;
+        // A bit-field may be unnamed. So is a struct or union without a tag,
+        // whose members are members of the enclosing one.
+        let is_anonymous = ty.kind == TypeKind::Struct && ty.tag.is_none() && i.starts_with(';');
+        let (i, (ident, mem_ty)) = if i.starts_with(':') || is_anonymous {
+            (i, ("", ty))
+        } else {
+            parse_declarator(i, ty)?
+        };
+        let (i, _) = multispace0(i)?;
+        let (i, colon) = opt(tag(":"))(i)?;
+        let (i, bit_width) = if colon.is_some() {
+            let (i, _) = multispace0(i)?;
+            let (i, width) = parse_const_expr(i)?;
+            let width = u16::try_from(width).unwrap_or(u16::MAX);
+            if !mem_ty.is_integer() {
+                return context("Invalid bit-field type", fail)(i);
+            }
+            if width > mem_ty.size * 8 {
+                return context("Bit-field width too large", fail)(i);
+            }
+            if width == 0 && !ident.is_empty() {
+                return context("Named bit-field of zero width", fail)(i);
+            }
+            (i, Some(width))
+        } else {
+            (i, None)
+        };
+        if mem_ty.is_incomplete() || mem_ty.is_function() {
+            return context("Member has incomplete type", fail)(i);
+        }
         let (i, _) = multispace0(i)?;
+        let (i, mem_attrs) = parse_attributes(i)?;
+        let mem_ty = aligned_type(&mem_ty, &mem_attrs);
         let (i, _) = tag(";")(i)?;
-        members.push(Member {
-            ty: Box::new(mem_ty.clone()),
-            name: ident.to_string(),
-            offset,
-        });
-        offset += mem_ty.size;
+        members.push((
+            Member {
+                ty: Box::new(mem_ty),
+                name: ident.to_string(),
+                offset: 0,
+                bit_offset: 0,
+                bit_width: bit_width.unwrap_or(0),
+            },
+            bit_width.is_some(),
+        ));
         t = i;
     }
+    // Only the last member may be an array without a size, which takes no space
+    // and addresses the data following the struct
+    let is_flexible = |member: &Member| member.ty.kind == TypeKind::Array && member.ty.size == 0;
+    if let Some(pos) = members.iter().position(|(member, _)| is_flexible(member)) {
+        if pos + 1 != members.len() {
+            return context("Flexible array member not at end of struct", fail)(t);
+        }
+        if pos == 0 {
+            return context("Flexible array member in otherwise empty struct", fail)(t);
+        }
+    }
+    let (i, _) = multispace0(t)?;
+    let (j, post) = parse_attributes(i)?;
+    if j != i {
+        attrs = attrs.merge(post);
+        t = j;
+    }
+
+    // Each member is placed at the next multiple of its alignment, and the size
+    // is padded to a multiple of the largest one so that arrays stay aligned.
+    // Members of a packed struct have no padding and are accessed byte by byte.
+    // Consecutive bit-fields share a storage unit of their declared type from
+    // the LSB up while they fit, so a bit-field never straddles two units.
+    // A unit is aligned like its type and a zero width bit-field closes it.
+    let mut offset = 0;
+    let mut align = 1;
+    let mut unit: Option<(u16, u16, u16)> = None; // (offset, size, used bits)
+    for (member, is_bitfield) in members.iter_mut() {
+        if attrs.is_packed {
+            *member.ty = member.ty.unaligned();
+        }
+        if is_union {
+            // All members overlap and the union is as large as the largest one
+            offset = offset.max(member.ty.size);
+        } else if *is_bitfield {
+            let size = member.ty.size;
+            match unit {
+                _ if member.bit_width == 0 => {
+                    unit = None;
+                    continue;
+                }
+                Some((unit_offset, unit_size, used))
+                    if unit_size == size && used + member.bit_width <= size * 8 =>
+                {
+                    member.offset = unit_offset;
+                    member.bit_offset = used;
+                    unit = Some((unit_offset, size, used + member.bit_width));
+                }
+                _ => {
+                    offset = align_to(offset, member.ty.align);
+                    member.offset = offset;
+                    unit = Some((offset, size, member.bit_width));
+                    offset += size;
+                }
+            }
+        } else {
+            unit = None;
+            offset = align_to(offset, member.ty.align);
+            member.offset = offset;
+            offset += member.ty.size;
+        }
+        align = align.max(member.ty.align);
+    }
+    // aligned may only raise the alignment, which also pads the size
+    align = align.max(attrs.aligned.unwrap_or(1));
+    // Unnamed bit-fields are only padding
+    let members: Vec<Member> = members
+        .into_iter()
+        .filter(|(member, is_bitfield)| !(*is_bitfield && member.name.is_empty()))
+        .map(|(member, _)| member)
+        .collect();
 
     let ty = Type {
         kind: TypeKind::Struct,
         ptr_to: None,
-        size: offset,
+        size: align_to(offset, align),
+        align,
         members: Some(members),
+        is_unsigned: false,
+        is_const: false,
+        is_volatile: false,
+        params: None,
+        is_variadic: false,
+        tag: ident.map(|ident| ident.to_string()),
+        is_union,
     };
     if let Some(ident) = ident {
+        declare_tag(t, ident, ty.clone())?;
+    }
+    Ok((t, ty))
+}
+
+// The type of a struct tag visible here
+fn lookup_tag(ident: &str) -> Option<Type> {
+    unsafe {
+        LOCALS
+            .iter()
+            .rev()
+            .chain(GLOBALS.iter())
+            .find(|&scope| scope.kind == ScopeKind::Tag && scope.name == ident)
+            .map(|tag| *tag.ty.clone().unwrap())
+    }
+}
+
+// Declare a struct tag in the current scope. The definition of a tag declared
+// incomplete in the same scope completes it.
+fn declare_tag<'a>(
+    text: &'a str,
+    ident: &str,
+    ty: Type,
+) -> IResult<&'a str, (), VerboseError<&'a str>> {
+    unsafe {
+        let scopes = if IS_GLOBAL {
+            &mut GLOBALS[..]
+        } else {
+            &mut LOCALS[BLOCK_START..]
+        };
+        if let Some(tag) = scopes
+            .iter_mut()
+            .find(|scope| scope.kind == ScopeKind::Tag && scope.name == ident)
+        {
+            let prev = tag.ty.as_ref().unwrap();
+            if prev.kind != ty.kind || prev.is_union != ty.is_union {
+                return tag_kind_error(text, ident);
+            }
+            if prev.is_incomplete() {
+                tag.ty = Some(Box::new(ty));
+                return Ok((text, ()));
+            }
+            if prev.kind == TypeKind::Struct && !ty.is_incomplete() {
+                return context("Redefinition of struct", fail)(text);
+            }
+        }
+        let scope = Scope {
+            kind: ScopeKind::Tag,
+            ty: Some(Box::new(ty)),
+            name: ident.to_string(),
+            str: None,
+            offset: None,
+            init: None,
+            storage: Storage::Default,
+            attrs: Attrs::default(),
+            is_defined: false,
+        };
+        if IS_GLOBAL {
+            GLOBALS.push(scope);
+        } else {
+            check_local(text, ident, ScopeKind::Tag)?;
+            LOCALS.push(scope);
+        }
+    }
+    Ok((text, ()))
+}
+
+// Check that a tag is used for the kind of type it was declared for. An enum
+// tag declares an int, and struct and union tags a struct.
+fn check_tag_kind<'a>(
+    text: &'a str,
+    ident: &str,
+    prev: &Type,
+    is_union: bool,
+) -> IResult<&'a str, (), VerboseError<&'a str>> {
+    if prev.kind != TypeKind::Struct || prev.is_union != is_union {
+        return tag_kind_error(text, ident);
+    }
+    Ok((text, ()))
+}
+
+fn tag_kind_error<'a, T>(text: &'a str, ident: &str) -> IResult<&'a str, T, VerboseError<&'a str>> {
+    let msg = format!(
+        "use of '{}' with tag type that does not match previous declaration",
+        ident
+    );
+    context(error_msg(msg), fail)(text)
+}
+
+// enum_decl = ident? ("{" ident ("=" const_expr)? ("," ident ("=" const_expr)?)* ","? "}")?
+// The constants are ints, numbered from 0 or from the last given value.
+fn parse_enum_decl(text: &str) -> IResult<&str, Type, VerboseError<&str>> {
+    let (i, ident) = opt(take_while1(is_ident))(text)?;
+    let (i, _) = multispace0(i)?;
+    let (mut t, bracket) = opt(tag("{"))(i)?;
+    if bracket.is_none() {
+        return match ident.map(|ident| (ident, lookup_tag(ident))) {
+            Some((_, Some(ty))) if ty.kind == TypeKind::Int => Ok((i, ty)),
+            Some((ident, Some(_))) => tag_kind_error(i, ident),
+            _ => context("Unknown enum type", fail)(i),
+        };
+    }
+    let mut val = 0;
+    loop {
+        let (i, _) = parse_space_or_comment(t)?;
+        let (i, s) = opt(tag("}"))(i)?;
+        if s.is_some() {
+            t = i;
+            break;
+        }
+        let (i, name) = take_while1(is_ident)(i)?;
+        let (i, _) = multispace0(i)?;
+        let (i, s) = opt(tag("="))(i)?;
+        let i = if s.is_some() {
+            let (i, _) = multispace0(i)?;
+            let (i, n) = parse_const_expr(i)?;
+            val = n;
+            i
+        } else {
+            i
+        };
+        let scope = Scope {
+            kind: ScopeKind::Enum,
+            ty: Some(Box::new(create_int_type())),
+            name: name.to_string(),
+            str: None,
+            offset: Some(val as u16),
+            init: None,
+            storage: Storage::Default,
+            attrs: Attrs::default(),
+            is_defined: false,
+        };
         unsafe {
             if IS_GLOBAL {
-                GLOBALS.push(Scope {
-                    kind: ScopeKind::Tag,
-                    ty: Some(Box::new(ty.clone())),
-                    name: ident.to_string(),
-                    str: None,
-                    offset: None,
-                })
+                GLOBALS.push(scope);
             } else {
-                LOCALS.push(Scope {
-                    kind: ScopeKind::Tag,
-                    ty: Some(Box::new(ty.clone())),
-                    name: ident.to_string(),
-                    str: None,
-                    offset: None,
-                })
+                check_local(i, name, ScopeKind::Enum)?;
+                LOCALS.push(scope);
             }
         }
+        val += 1;
+        let (i, _) = parse_space_or_comment(i)?;
+        let (i, s) = opt(tag(","))(i)?;
+        if s.is_none() {
+            let (i, _) = parse_space_or_comment(i)?;
+            let (i, _) = tag("}")(i)?;
+            t = i;
+            break;
+        }
+        t = i;
+    }
+    let ty = create_int_type();
+    if let Some(ident) = ident {
+        declare_tag(t, ident, ty.clone())?;
     }
     Ok((t, ty))
 }
 
-// declarator = "*"* ident ("[" num "]")*
-fn parse_declarator(text: &str, ty: Type) -> IResult<&str, (&str, Type), VerboseError<&str>> {
-    let (i, cnt) = many0_count(tag("*"))(text)?;
-    let mut ty = ty;
-    for _ in 0..cnt {
-        ty = Type {
-            kind: TypeKind::Pointer,
-            ptr_to: Some(Box::new(ty)),
-            size: 2,
-            members: None,
-        }
+// The value of an enumeration constant, unless a variable hides it
+fn find_enum_const(ident: &str) -> Option<i32> {
+    let is_ordinary = |scope: &&Scope| {
+        matches!(
+            scope.kind,
+            ScopeKind::Var | ScopeKind::Typedef | ScopeKind::Enum
+        ) && scope.name == ident
+    };
+    unsafe {
+        let scope = match LOCALS.iter().rev().find(is_ordinary) {
+            Some(scope) => scope,
+            None => GLOBALS.iter().rev().find(is_ordinary)?,
+        };
+        (scope.kind == ScopeKind::Enum).then(|| scope.offset.unwrap() as i16 as i32)
     }
-    let (i, _) = multispace0(i)?;
-    let (i, ident) = take_while1(is_ident)(i)?;
+}
 
+// const_expr = logor, which must evaluate to a number
+fn parse_const_expr(text: &str) -> IResult<&str, i32, VerboseError<&str>> {
+    let (i, node) = parse_logor(text)?;
+    match eval(&node) {
+        Some(val) if node.ty.as_ref().is_some_and(|ty| ty.is_uint()) => Ok((i, val)),
+        Some(val) => Ok((i, val as i16 as i32)),
+        None => context("Not a constant expression", fail)(text),
+    }
+}
+
+// static_assert = "_Static_assert" "(" const_expr ("," str)? ")"
+fn parse_static_assert(text: &str) -> IResult<&str, (), VerboseError<&str>> {
+    let (i, _) = tag("_Static_assert")(text)?;
     let (i, _) = multispace0(i)?;
-    let (i, num) = many0(delimited(
-        tag("["),
-        delimited(multispace0, digit1, multispace0),
-        tag("]"),
-    ))(i)?;
-    for n in num.iter().rev() {
-        let n = n.parse::<u16>().unwrap();
-        ty = Type {
-            kind: TypeKind::Array,
-            ptr_to: Some(Box::new(ty.clone())),
-            size: ty.size * n,
-            members: None,
-        }
+    let (i, _) = tag("(")(i)?;
+    let (i, _) = multispace0(i)?;
+    let (i, val) = parse_const_expr(i)?;
+    let (i, _) = multispace0(i)?;
+    let (i, s) = opt(tag(","))(i)?;
+    let (i, msg) = if s.is_some() {
+        let (i, _) = multispace0(i)?;
+        let (i, msg) = parse_str_literal(i)?;
+        (i, Some(msg))
+    } else {
+        (i, None)
+    };
+    let (i, _) = multispace0(i)?;
+    let (i, _) = tag(")")(i)?;
+    if val == 0 {
+        return match msg {
+            Some(msg) => {
+                let msg = format!("Static assertion failed: {}", msg.trim_end_matches('\0'));
+                context(error_msg(msg), fail)(i)
+            }
+            None => context("Static assertion failed", fail)(i),
+        };
     }
+    Ok((i, ()))
+}
 
-    Ok((i, (ident, ty)))
+fn is_static_assert(text: &str) -> bool {
+    let ident: IResult<&str, &str, VerboseError<&str>> = take_while1(is_ident)(text);
+    matches!(ident, Ok((_, "_Static_assert")))
 }
 
-fn parse_global(text: &str) -> IResult<&str, Scope, VerboseError<&str>> {
-    unsafe { IS_GLOBAL = true }
-    let (i, ty) = parse_declspec(text)?;
-    let (i, _) = multispace0(i)?;
+// An incomplete struct is complete once its tag has been defined
+fn complete_type(ty: Type) -> Type {
+    if !ty.is_incomplete() {
+        return ty;
+    }
+    match lookup_tag(ty.tag.as_ref().unwrap()) {
+        Some(tag) if !tag.is_incomplete() && tag.tag == ty.tag => tag.qualified_by(&ty),
+        _ => ty,
+    }
+}
 
-    // if declaration only
-    let (i, s) = opt(tag(";"))(i)?;
+// attributes = ("__attribute__" "((" (attribute ("," attribute)*)? "))")*
+// attribute = ident ("(" (str | const_expr) ")")?
+// Unknown attributes without arguments are ignored.
+fn parse_attributes(text: &str) -> IResult<&str, Attrs, VerboseError<&str>> {
+    let mut attrs = Attrs::default();
+    let mut t = text;
+    while let (i, Some(_)) = opt(tag("__attribute__"))(t)? {
+        let (i, _) = multispace0(i)?;
+        let (mut i, _) = tag("((")(i)?;
+        loop {
+            let (j, _) = multispace0(i)?;
+            let (j, ident) = opt(take_while1(is_ident))(j)?;
+            let Some(ident) = ident else {
+                i = j;
+                break;
+            };
+            let (j, _) = multispace0(j)?;
+            let (j, paren) = opt(tag("("))(j)?;
+            let (j, _) = multispace0(j)?;
+            let j = match (ident, paren) {
+                // There are no real sections, only a label in front of the
+                // group, so the name must be a label that isn't generated
+                ("section", Some(_)) => {
+                    let (k, name) = parse_str_literal(j)?;
+                    let name = name.trim_end_matches('\0');
+                    if name.is_empty()
+                        || name.starts_with(|c: char| c.is_ascii_digit())
+                        || !name.chars().all(is_ident)
+                    {
+                        return context("Section name is not a valid label", fail)(j);
+                    }
+                    if matches!(name, "init" | "main" | "heap" | "__data" | "__rodata") {
+                        return context("Section name is reserved", fail)(j);
+                    }
+                    attrs.section = Some(name.to_string());
+                    k
+                }
+                ("aligned", Some(_)) => {
+                    let (k, align) = parse_const_expr(j)?;
+                    if align <= 0 || align & (align - 1) != 0 {
+                        return context("Requested alignment is not a power of 2", fail)(j);
+                    }
+                    attrs.aligned = attrs.aligned.max(Some(align as u16));
+                    k
+                }
+                // The largest alignment any type needs
+                ("aligned", None) => {
+                    attrs.aligned = attrs.aligned.max(Some(2));
+                    j
+                }
+                ("naked", None) => {
+                    attrs.is_naked = true;
+                    j
+                }
+                ("noreturn", None) => {
+                    attrs.is_noreturn = true;
+                    j
+                }
+                ("used", None) => {
+                    attrs.is_used = true;
+                    j
+                }
+                ("interrupt", None) => {
+                    attrs.is_interrupt = true;
+                    j
+                }
+                ("packed", None) => {
+                    attrs.is_packed = true;
+                    j
+                }
+                (_, Some(_)) | ("section", None) => return context("Invalid attribute", fail)(i),
+                _ => j,
+            };
+            let (j, _) = multispace0(j)?;
+            let j = match paren {
+                Some(_) => tag(")")(j)?.0,
+                None => j,
+            };
+            let (j, _) = multispace0(j)?;
+            let (j, comma) = opt(tag(","))(j)?;
+            i = j;
+            if comma.is_none() {
+                break;
+            }
+        }
+        let (i, _) = tag("))")(i)?;
+        let (i, _) = multispace0(i)?;
+        t = i;
+    }
+    Ok((t, attrs))
+}
+
+// The stack is only word aligned, so a local can't be aligned any further
+const STACK_ALIGN: u16 = 2;
+
+// The type of the storage of a variable, aligned as its attributes ask
+fn aligned_type(ty: &Type, attrs: &Attrs) -> Type {
+    Type {
+        align: ty.align.max(attrs.aligned.unwrap_or(1)),
+        ..ty.clone()
+    }
+}
+
+// Fail if a local of type ty needs more alignment than its stack slot can have
+fn check_stack_align<'a>(text: &'a str, ty: &Type) -> IResult<&'a str, (), VerboseError<&'a str>> {
+    if ty.align > STACK_ALIGN {
+        return context(
+            "Requested alignment is larger than the stack alignment",
+            fail,
+        )(text);
+    }
+    Ok((text, ()))
+}
+
+// Reserve an aligned stack slot for a local and return its offset from fp
+fn alloc_local(ty: &Type) -> u16 {
+    unsafe {
+        LOCAL_OFFSET = align_to(LOCAL_OFFSET + ty.size, ty.align);
+        LOCAL_OFFSET
+    }
+}
+
+// declarator = pointers ("(" declarator ")" | ident) type_suffix
+fn parse_declarator(text: &str, ty: Type) -> IResult<&str, (&str, Type), VerboseError<&str>> {
+    let (i, ty) = parse_pointers(text, ty)?;
+    if let Some(inner) = nested_declarator(i) {
+        // The suffix applies before the inner declarator, e.g. in int (*fp)(int),
+        // so skip over the inner one and parse it again with the complete type
+        let (rest, _) = parse_declarator(inner, create_int_type())?;
+        let (rest, _) = tag(")")(rest)?;
+        let (rest, _) = multispace0(rest)?;
+        let (rest, ty) = parse_type_suffix(rest, ty)?;
+        let (_, (ident, ty)) = parse_declarator(inner, ty)?;
+        return Ok((rest, (ident, ty)));
+    }
+    let (i, ident) = take_while1(is_ident)(i)?;
+    let (i, _) = multispace0(i)?;
+    let (i, ty) = parse_type_suffix(i, ty)?;
+
+    Ok((i, (ident, ty)))
+}
+
+// abstract_declarator = pointers ("(" abstract_declarator ")")? type_suffix
+fn parse_abstract_declarator(text: &str, ty: Type) -> IResult<&str, Type, VerboseError<&str>> {
+    let (i, ty) = parse_pointers(text, ty)?;
+    if let Some(inner) = nested_declarator(i) {
+        let (rest, _) = parse_abstract_declarator(inner, create_int_type())?;
+        let (rest, _) = tag(")")(rest)?;
+        let (rest, _) = multispace0(rest)?;
+        let (rest, ty) = parse_type_suffix(rest, ty)?;
+        let (_, ty) = parse_abstract_declarator(inner, ty)?;
+        return Ok((rest, ty));
+    }
+    parse_type_suffix(i, ty)
+}
+
+// The text after "(" if it starts a parenthesized declarator rather than a
+// parameter list
+fn nested_declarator(text: &str) -> Option<&str> {
+    let inner = text.strip_prefix('(')?.trim_start();
+    (inner.starts_with('*') || inner.starts_with('(')).then_some(inner)
+}
+
+// pointers = ("*" )*
+fn parse_pointers(text: &str, ty: Type) -> IResult<&str, Type, VerboseError<&str>> {
+    let (i, quals) = many0(preceded(
+        delimited(multispace0, tag("*"), multispace0),
+        parse_qualifiers,
+    ))(text)?;
+    let mut ty = ty;
+    for qual in quals {
+        ty = create_pointer_type(ty).qualified_by(&qual);
+    }
+    let (i, _) = multispace0(i)?;
+    Ok((i, ty))
+}
+
+// type_suffix = "(" params ")" | ("[" num? "]")*
+// An array without size has size 0 until it is completed by an initializer.
+fn parse_type_suffix(text: &str, ty: Type) -> IResult<&str, Type, VerboseError<&str>> {
+    let (i, s) = opt(tag("("))(text)?;
+    if s.is_some() {
+        let (i, (params, is_variadic)) = parse_param_types(i)?;
+        let (i, _) = multispace0(i)?;
+        return Ok((i, create_function_type(ty, params, is_variadic)));
+    }
+    let mut dims = Vec::new();
+    let mut t = text;
+    loop {
+        let (i, s) = opt(tag("["))(t)?;
+        if s.is_none() {
+            break;
+        }
+        let (i, _) = multispace0(i)?;
+        // The size may be left to the initializer or the definition
+        let (i, n) = if i.starts_with(']') {
+            (i, 0)
+        } else {
+            parse_const_expr(i)?
+        };
+        if n < 0 {
+            return context("Array size is negative", fail)(i);
+        }
+        let (i, _) = multispace0(i)?;
+        let (i, _) = tag("]")(i)?;
+        dims.push(n as u16);
+        t = i;
+    }
+    let mut ty = ty;
+    for n in dims.into_iter().rev() {
+        if ty.size.checked_mul(n).is_none() {
+            return context("Array too large", fail)(text);
+        }
+        ty = create_array_type(ty, n);
+    }
+    Ok((t, ty))
+}
+
+// params = (param ("," param)*)? variadic ")"
+// param = declspec (declarator | abstract_declarator)
+fn parse_param_types(text: &str) -> IResult<&str, (Vec<Type>, bool), VerboseError<&str>> {
+    let (i, _) = multispace0(text)?;
+    let (i, params) = separated_list0(delimited(multispace0, tag(","), multispace0), |i| {
+        let (i, ty) = parse_declspec(i)?;
+        let (i, _) = multispace0(i)?;
+        match parse_declarator(i, ty.clone()) {
+            Ok((i, (_, ty))) => Ok((i, ty)),
+            Err(_) => parse_abstract_declarator(i, ty),
+        }
+    })(i)?;
+    let (i, is_variadic) = parse_variadic(i)?;
+    let (i, _) = multispace0(i)?;
+    let (i, _) = tag(")")(i)?;
+    let params = params.into_iter().map(param_type).collect();
+    Ok((i, (params, is_variadic)))
+}
+
+// variadic = ("," "...")?
+fn parse_variadic(text: &str) -> IResult<&str, bool, VerboseError<&str>> {
+    let (i, dots) = opt(preceded(
+        delimited(multispace0, tag(","), multispace0),
+        tag("..."),
+    ))(text)?;
+    Ok((i, dots.is_some()))
+}
+
+// An array parameter is a pointer to its first element, and a function
+// parameter is a pointer to the function
+fn param_type(ty: Type) -> Type {
+    match ty.kind {
+        TypeKind::Array => create_pointer_type(*ty.ptr_to.unwrap()),
+        _ if ty.is_function() => create_pointer_type(ty),
+        _ => ty,
+    }
+}
+
+// An array or function argument is passed as a pointer
+fn decay(node: Node) -> Node {
+    let ty = node.ty.clone().unwrap();
+    if ty.kind != TypeKind::Array && !ty.is_function() {
+        return node;
+    }
+    Node {
+        ty: Some(Box::new(param_type(*ty))),
+        kind: NodeKind::Cast(Unary {
+            unary: Box::new(node),
+        }),
+    }
+}
+
+fn parse_global(text: &str) -> IResult<&str, Scope, VerboseError<&str>> {
+    unsafe {
+        IS_GLOBAL = true;
+        LOCALS = vec![];
+    }
+    let (i, attrs) = parse_attributes(text)?;
+    let (i, storage) = parse_storage(i)?;
+    // _Static_assert declares nothing, like a declaration without a declarator
+    let (i, ty) = if is_static_assert(i) {
+        let (i, _) = parse_static_assert(i)?;
+        let (i, _) = multispace0(i)?;
+        let (i, _) = peek(tag(";"))(i)?;
+        (i, None)
+    } else {
+        let (i, ty) = parse_declspec(i)?;
+        (i, Some(ty))
+    };
+    let (i, _) = multispace0(i)?;
+
+    // if declaration only
+    let (i, s) = opt(tag(";"))(i)?;
     if s.is_some() {
         return Ok((
             i,
@@ -505,14 +1386,37 @@ fn parse_global(text: &str) -> IResult<&str, Scope, VerboseError<&str>> {
                 name: "".to_string(),
                 str: None,
                 offset: None,
+                init: None,
+                storage: Storage::Default,
+                attrs: Attrs::default(),
+                is_defined: false,
             },
         ));
     }
 
-    let (i, (ident, ty)) = parse_declarator(i, ty)?;
+    let (i, (ident, ty)) = parse_declarator(i, ty.unwrap())?;
+    let (i, _) = multispace0(i)?;
+    let (i, post) = parse_attributes(i)?;
+    let attrs = attrs.merge(post);
+    if ty.is_function() {
+        // A body makes it a function definition, whose errors parse_function reports
+        if peek(tag::<_, _, VerboseError<&str>>("{"))(i).is_ok() {
+            return fail(text);
+        }
+        let (i, _) = tag(";")(i)?;
+        return new_function_decl(i, ident, ty, storage, attrs);
+    }
+    let (i, (ty, init)) = parse_opt_global_init(i, ty, storage)?;
     let (i, _) = multispace0(i)?;
     let (i, _) = tag(";")(i)?;
 
+    // An extern declaration with an initializer is a definition
+    let storage = match storage {
+        Some("extern") if init.is_some() => Storage::Default,
+        _ => new_storage(storage, ident),
+    };
+    check_global(i, ident, &ty, &storage)?;
+
     Ok((
         i,
         Scope {
@@ -521,30 +1425,480 @@ fn parse_global(text: &str) -> IResult<&str, Scope, VerboseError<&str>> {
             name: ident.to_string(),
             str: None,
             offset: None,
+            init,
+            storage,
+            attrs,
+            is_defined: false,
         },
     ))
 }
 
+// Type and initial data of a variable in data memory
+type GlobalInit = (Type, Option<Vec<InitData>>);
+
+// ("=" initializer)? of a variable in data memory
+fn parse_opt_global_init<'a>(
+    text: &'a str,
+    ty: Type,
+    storage: Option<&str>,
+) -> IResult<&'a str, GlobalInit, VerboseError<&'a str>> {
+    if ty.is_incomplete() && storage != Some("extern") {
+        return context("Variable has incomplete type", fail)(text);
+    }
+    let (i, s) = opt(tag("="))(text)?;
+    let (i, (ty, init)) = if s.is_some() {
+        let (i, _) = multispace0(i)?;
+        let (i, (ty, init)) = parse_global_init(i, &ty)?;
+        (i, (ty, Some(init)))
+    } else {
+        (i, (ty, None))
+    };
+    // extern arrays may leave the size to the definition
+    if ty.kind == TypeKind::Array && ty.size == 0 && storage != Some("extern") {
+        return context("Array size missing", fail)(i);
+    }
+
+    Ok((i, (ty, init)))
+}
+
+// The type pointed to by the value of node, if it is a pointer
+fn pointee(node: &Node) -> Option<Type> {
+    let ty = node.ty.clone()?;
+    match ty.kind {
+        TypeKind::Pointer | TypeKind::Array => Some(*ty.ptr_to.unwrap()),
+        _ if ty.is_function() => Some(*ty),
+        _ => None,
+    }
+}
+
+// An integer constant expression with the value 0, e.g. NULL
+fn is_null_pointer(node: &Node) -> bool {
+    node.ty.as_ref().is_some_and(|ty| ty.is_integer()) && eval(node) == Some(0)
+}
+
+// Warn when a value is assigned to a pointer of a different type
+fn check_pointer_types(text: &str, left: &Type, right: &Node) {
+    if left.kind != TypeKind::Pointer {
+        return;
+    }
+    let msg = match pointee(right) {
+        Some(from) if !left.ptr_to.as_ref().unwrap().is_compatible_pointee(&from) => {
+            "assignment to pointer from incompatible pointer type"
+        }
+        None if !is_null_pointer(right) && right.ty.as_ref().is_some_and(|ty| ty.is_integer()) => {
+            "assignment makes pointer from integer without a cast"
+        }
+        _ => return,
+    };
+    warn(text, WarningKind::PointerTypes, msg.to_string());
+}
+
+// Warn when a pointer is compared with a pointer of a different type or
+// with an integer other than a null pointer constant
+fn check_pointer_cmp(text: &str, left: &Node, right: &Node) {
+    let msg = match (pointee(left), pointee(right)) {
+        (Some(l), Some(r)) if !l.is_compatible_pointee(&r) => {
+            "comparison of distinct pointer types lacks a cast"
+        }
+        (Some(_), None) if !is_null_pointer(right) => "comparison between pointer and integer",
+        (None, Some(_)) if !is_null_pointer(left) => "comparison between pointer and integer",
+        _ => return,
+    };
+    warn(text, WarningKind::PointerTypes, msg.to_string());
+}
+
+// Warn when a pointer assignment drops qualifiers of the pointed-to type
+fn check_qualifiers(text: &str, left: &Type, right: &Node) {
+    let right = right.ty.clone().unwrap();
+    if left.kind != TypeKind::Pointer || !right.is_pointer() {
+        return;
+    }
+    let (to, from) = (left.ptr_to.clone().unwrap(), right.ptr_to.unwrap());
+    if (from.is_const && !to.is_const) || (from.is_volatile && !to.is_volatile) {
+        warn(
+            text,
+            WarningKind::DiscardedQualifiers,
+            "assignment discards qualifiers from pointer target type".to_string(),
+        );
+    }
+}
+
+// Type of the result of usual arithmetic conversions
+fn arith_type(left: &Node, right: &Node) -> Type {
+    let is_uint = |node: &Node| node.ty.as_ref().is_some_and(|ty| ty.is_uint());
+    if is_uint(left) || is_uint(right) {
+        create_uint_type()
+    } else {
+        create_int_type()
+    }
+}
+
+// Initializer of a scalar element. The element is described as a member
+// of the whole variable.
+type InitItem = (Member, Node);
+
+// Evaluate initializer items into the bytes of a global variable
+fn parse_global_init<'a>(
+    text: &'a str,
+    ty: &Type,
+) -> IResult<&'a str, (Type, Vec<InitData>), VerboseError<&'a str>> {
+    let (i, (ty, inits)) = parse_initializer(text, ty)?;
+    // Later initializers override earlier ones for the same element
+    let mut items = BTreeMap::new();
+    for (member, node) in inits {
+        items.insert((member.offset, member.bit_offset), (member, node));
+    }
+    let mut bytes = vec![0; ty.size as usize];
+    let mut labels = BTreeMap::new();
+    for (member, node) in items.into_values() {
+        let offset = member.offset as usize;
+        match eval_addr(&node) {
+            Some((Some(label), val)) if member.ty.size == 2 && !member.is_bitfield() => {
+                if !is_in_object(&label, val as i16) {
+                    return context("Initializer address out of bounds", fail)(i);
+                }
+                labels.insert(offset, (label, val as u16));
+            }
+            Some((None, val)) => {
+                labels.remove(&offset);
+                let mut mask = 0xffff;
+                let mut val = val;
+                if member.is_bitfield() {
+                    mask = ((1 << member.bit_width) - 1) << member.bit_offset;
+                    val <<= member.bit_offset;
+                }
+                for k in 0..member.ty.size as usize {
+                    let m = (mask >> (8 * k)) as u8;
+                    bytes[offset + k] = (bytes[offset + k] & !m) | ((val >> (8 * k)) as u8 & m);
+                }
+            }
+            _ => return context("Initializer is not a constant", fail)(i),
+        }
+    }
+    // An address takes two bytes
+    let mut data = Vec::new();
+    let mut offset = 0;
+    while offset < bytes.len() {
+        if let Some((label, val)) = labels.remove(&offset) {
+            data.push(InitData::Label(label, val));
+            offset += 2;
+        } else {
+            data.push(InitData::Byte(bytes[offset]));
+            offset += 1;
+        }
+    }
+    Ok((i, (ty, data)))
+}
+
+// Whether an address at offset from a label points into its object or just
+// past its end. An offset is emitted as a label defined inside the object,
+// which only exists if it is defined in this program, so only the start of an
+// extern object can be addressed.
+fn is_in_object(label: &str, offset: i16) -> bool {
+    let global = unsafe {
+        GLOBALS
+            .iter()
+            .find(|g| g.name == label || matches!(&g.storage, Storage::Static(l) if l == label))
+    };
+    let size = match global {
+        Some(g) if g.kind == ScopeKind::Func || g.storage == Storage::Extern => 0,
+        Some(g) => g.ty.as_ref().unwrap().size as i16,
+        None => i16::MAX,
+    };
+    (0..=size).contains(&offset)
+}
+
+// Initializer items (offset, type, expr) for a variable of type ty.
+// The returned type has the array size inferred from the initializer.
+fn parse_initializer<'a>(
+    text: &'a str,
+    ty: &Type,
+) -> IResult<&'a str, (Type, Vec<InitItem>), VerboseError<&'a str>> {
+    let mut inits = Vec::new();
+    let (i, len) = parse_initializer_at(text, &new_member(ty.clone(), 0), &mut inits)?;
+    let ty = if ty.kind == TypeKind::Array && ty.size == 0 {
+        let base = *ty.ptr_to.clone().unwrap();
+        if base.size.checked_mul(len).is_none() {
+            return context("Array too large", fail)(text);
+        }
+        create_array_type(base, len)
+    } else {
+        ty.clone()
+    };
+    Ok((i, (ty, inits)))
+}
+
+// initializer = "{" (designation? initializer ("," designation? initializer)* ","?)? "}"
+//             | str
+//             | assign
+// Initializes target, an element at an offset from the start of the variable.
+// Returns the number of initialized elements.
+fn parse_initializer_at<'a>(
+    text: &'a str,
+    target: &Member,
+    inits: &mut Vec<InitItem>,
+) -> IResult<&'a str, u16, VerboseError<&'a str>> {
+    let ty = &*target.ty;
+    let offset = target.offset;
+    match ty.kind {
+        TypeKind::Array | TypeKind::Struct => {
+            let (i, bracket) = opt(tag("{"))(text)?;
+            if bracket.is_none() && ty.kind == TypeKind::Struct {
+                // Copy of another struct
+                let (i, node) = parse_assign(text)?;
+                if !ty.is_same_struct(&node.ty.clone().unwrap()) {
+                    return context("Invalid initializer", fail)(text);
+                }
+                inits.push((target.clone(), node));
+                return Ok((i, 1));
+            }
+            if bracket.is_none() {
+                // char array initialized by a string literal
+                let (_, s) = opt(tag("\""))(text)?;
+                if s.is_none() || ty.kind != TypeKind::Array || !is_char_array(ty) {
+                    return context("Invalid initializer", fail)(text);
+                }
+                let (i, str) = parse_str_literal(text)?;
+                let mut len = str.chars().count() as u16;
+                if ty.size != 0 {
+                    // The terminating '\0' is dropped if the array is just long enough
+                    if len > ty.size + 1 {
+                        return context("Initializer string is too long", fail)(i);
+                    }
+                    len = len.min(ty.size);
+                }
+                for (k, c) in str.chars().take(len as usize).enumerate() {
+                    let elem = new_member(create_char_type(), offset + k as u16);
+                    inits.push((elem, new_num(c as i32)));
+                }
+                return Ok((i, len));
+            }
+
+            let mut t = i;
+            let mut idx = 0;
+            let mut len = 0;
+            loop {
+                let (i, _) = parse_space_or_comment(t)?;
+                let (i, s) = opt(tag("}"))(i)?;
+                if s.is_some() {
+                    t = i;
+                    break;
+                }
+                let (_, designator) = opt(alt((tag("."), tag("["))))(i)?;
+                let i = if designator.is_some() {
+                    let (i, d) = parse_designator(i, ty)?;
+                    idx = d;
+                    let member = match subobject(target, idx) {
+                        Some(member) => member,
+                        None => return context("Designator out of range", fail)(i),
+                    };
+                    parse_designation(i, &member, inits)?.0
+                } else {
+                    // Only the first member of a union is initialized in order
+                    let member = match subobject(target, idx) {
+                        Some(member) if !ty.is_union || idx == 0 => member,
+                        _ => return context("Too many initializers", fail)(i),
+                    };
+                    parse_initializer_at(i, &member, inits)?.0
+                };
+                idx += 1;
+                len = len.max(idx);
+
+                let (i, _) = parse_space_or_comment(i)?;
+                let (i, s) = opt(tag(","))(i)?;
+                if s.is_none() {
+                    let (i, _) = parse_space_or_comment(i)?;
+                    let (i, _) = tag("}")(i)?;
+                    t = i;
+                    break;
+                }
+                t = i;
+            }
+            Ok((t, len))
+        }
+        _ => {
+            let (i, bracket) = opt(tag("{"))(text)?;
+            let (i, _) = multispace0(i)?;
+            let (i, node) = parse_assign(i)?;
+            let (i, _) = multispace0(i)?;
+            let (i, _) = if bracket.is_some() {
+                tag("}")(i)?
+            } else {
+                (i, "")
+            };
+            inits.push((target.clone(), node));
+            Ok((i, 1))
+        }
+    }
+}
+
+// designation = designator* "=" initializer
+fn parse_designation<'a>(
+    text: &'a str,
+    target: &Member,
+    inits: &mut Vec<InitItem>,
+) -> IResult<&'a str, (), VerboseError<&'a str>> {
+    let (i, _) = multispace0(text)?;
+    let (i, s) = opt(tag("="))(i)?;
+    if s.is_some() {
+        let (i, _) = multispace0(i)?;
+        let (i, _) = parse_initializer_at(i, target, inits)?;
+        return Ok((i, ()));
+    }
+    let (i, idx) = parse_designator(i, &target.ty)?;
+    match subobject(target, idx) {
+        Some(member) => parse_designation(i, &member, inits),
+        None => context("Designator out of range", fail)(i),
+    }
+}
+
+// designator = "[" expr "]" | "." ident
+// Returns the index of the designated element or member.
+fn parse_designator<'a>(text: &'a str, ty: &Type) -> IResult<&'a str, u16, VerboseError<&'a str>> {
+    let (i, s) = alt((tag("["), tag(".")))(text)?;
+    let (i, _) = multispace0(i)?;
+    if s == "[" && ty.kind == TypeKind::Array {
+        let (i, node) = parse_expr(i)?;
+        let (i, _) = multispace0(i)?;
+        let (i, _) = tag("]")(i)?;
+        match eval(&node) {
+            Some(idx) => Ok((i, idx as u16)),
+            None => context("Array index is not a constant", fail)(i),
+        }
+    } else if s == "." && ty.kind == TypeKind::Struct {
+        let (i, ident) = take_while1(is_ident)(i)?;
+        let members = ty.members.clone().unwrap();
+        if let Some(idx) = members.iter().position(|member| member.name == ident) {
+            return Ok((i, idx as u16));
+        }
+        // A member of an anonymous member is designated inside it, so leave the
+        // designator to be parsed again there
+        match members
+            .iter()
+            .position(|member| member.name.is_empty() && member.ty.find_member(ident).is_some())
+        {
+            Some(idx) => Ok((text, idx as u16)),
+            None => context("Not such member", fail)(i),
+        }
+    } else {
+        context("Invalid designator", fail)(text)
+    }
+}
+
+// The idx-th element of an array or member of a struct, with its offset
+// from the start of the variable
+fn subobject(target: &Member, idx: u16) -> Option<Member> {
+    let ty = &target.ty;
+    if ty.kind == TypeKind::Array {
+        let elem_ty = *ty.ptr_to.clone().unwrap();
+        if ty.size != 0 && (idx + 1) * elem_ty.size > ty.size {
+            return None;
+        }
+        let offset = target.offset + idx * elem_ty.size;
+        Some(new_member(elem_ty, offset))
+    } else {
+        let members = ty.members.clone().unwrap();
+        let mut member = members.get(idx as usize)?.clone();
+        member.offset += target.offset;
+        Some(member)
+    }
+}
+
+// Unnamed element which is not a bit-field
+fn new_member(ty: Type, offset: u16) -> Member {
+    Member {
+        ty: Box::new(ty),
+        name: String::new(),
+        offset,
+        bit_offset: 0,
+        bit_width: 0,
+    }
+}
+
+fn is_char_array(ty: &Type) -> bool {
+    ty.ptr_to.as_ref().map(|ty| ty.kind) == Some(TypeKind::Char)
+}
+
+fn new_num(val: i32) -> Node {
+    Node {
+        kind: NodeKind::Num(Num { val }),
+        ty: Some(Box::new(create_int_type())),
+    }
+}
+
+// ptr + index, or ptr - index when negate is set. A constant index is folded
+// into a Num, together with the constant index of an inner ptr + n.
+fn new_ptr_add(ptr: Node, index: Node, negate: bool) -> Node {
+    let mut ty = ptr.ty.clone();
+    if let Some(ty) = ty.as_mut() {
+        ty.ptr_to = Some(Box::new(complete_type(*ty.ptr_to.clone().unwrap())));
+    }
+    let Some(val) = eval(&index) else {
+        let kind = if negate {
+            NodeKind::PtrSub(Binary {
+                left: Box::new(ptr),
+                right: Box::new(index),
+            })
+        } else {
+            NodeKind::PtrAdd(Binary {
+                left: Box::new(ptr),
+                right: Box::new(index),
+            })
+        };
+        return Node { kind, ty };
+    };
+    let mut val = val as i16 as i32;
+    if negate {
+        val = -val;
+    }
+    let mut left = ptr;
+    if let NodeKind::PtrAdd(n) = &left.kind {
+        if let NodeKind::Num(inner) = &n.right.kind {
+            if n.left.ty == left.ty {
+                val += inner.val;
+                left = *n.left.clone();
+            }
+        }
+    }
+    Node {
+        kind: NodeKind::PtrAdd(Binary {
+            left: Box::new(left),
+            right: Box::new(new_num(val as i16 as i32)),
+        }),
+        ty,
+    }
+}
+
 static mut LOCAL_OFFSET: u16 = 0;
+static mut MAX_LOCAL_OFFSET: u16 = 0;
+static mut BLOCK_START: usize = 0; // Index in LOCALS where the innermost block begins
+static mut RET_PTR: Option<Node> = None; // Hidden result pointer of a function returning a struct
+static mut IS_VARIADIC: bool = false;
+
+// Arguments are passed in a0-a2, including the hidden result pointer
+const MAX_REG_ARGS: usize = 3;
 
 fn parse_function(text: &str) -> IResult<&str, Func, VerboseError<&str>> {
     unsafe {
-        IS_GLOBAL = false;
+        IS_GLOBAL = true;
         LOCALS = vec![];
         LOCAL_OFFSET = 0;
+        MAX_LOCAL_OFFSET = 0;
+        BLOCK_START = 0;
+        RET_PTR = None;
     }
     let mut nodes: Vec<Node> = Vec::new();
     let (i, naked) = opt(tag("__naked__"))(text)?;
-    let mut is_naked = false;
-    if naked.is_some() {
-        is_naked = true;
-    }
-
     let (i, _) = multispace0(i)?;
+    let (i, mut attrs) = parse_attributes(i)?;
+    attrs.is_naked |= naked.is_some();
+    let (i, storage) = parse_storage(i)?;
+    // Tags declared by the return type are at file scope
     let (i, ty) = parse_declspec(i)?;
+    unsafe { IS_GLOBAL = false };
     let (i, _) = multispace0(i)?;
-    let (i, (ident, _)) = parse_declarator(i, ty)?; // Ignore function return types
-    let ty = create_func_type();
+    let (i, ret_ty) = parse_pointers(i, ty)?;
+    let (i, ident) = take_while1(is_ident)(i)?;
 
     let kind = if ident == "init" {
         FuncKind::Init
@@ -556,13 +1910,67 @@ fn parse_function(text: &str) -> IResult<&str, Func, VerboseError<&str>> {
 
     let (i, _) = multispace0(i)?;
     let (i, _) = tag("(")(i)?;
-    let (i, args) = separated_list0(
+    let (i, _) = multispace0(i)?;
+    let (i, mut args) = separated_list0(
         permutation((multispace0, tag(","), multispace0)),
         parse_funcparam,
     )(i)?;
+    let (i, is_variadic) = parse_variadic(i)?;
+    unsafe { IS_VARIADIC = is_variadic };
+    let params = args.iter().map(|arg| *arg.ty.clone().unwrap()).collect();
+    let ty = create_function_type(ret_ty.clone(), params, is_variadic);
     let (i, _) = multispace0(i)?;
     let (i, _) = tag(")")(i)?;
     let (i, _) = multispace0(i)?;
+    let (i, post) = parse_attributes(i)?;
+    attrs = attrs.merge(post);
+    for arg in &args {
+        check_stack_align(i, arg.ty.as_ref().unwrap())?;
+    }
+
+    // A function declared static keeps its label in the definition
+    let prev = check_function(i, ident, &ty)?.1;
+    if let Some(prev) = &prev {
+        if prev.is_defined {
+            return context("Redefinition of function", fail)(i);
+        }
+        attrs = attrs.merge(prev.attrs.clone());
+    }
+    // init and main always come first
+    if kind != FuncKind::Other && attrs.section.is_some() {
+        return context("Section is not allowed on init or main", fail)(i);
+    }
+    let storage = match prev.map(|prev| prev.storage) {
+        Some(Storage::Static(label)) => Storage::Static(label),
+        _ if storage == Some("static") => new_storage(storage, ident),
+        _ => Storage::Default,
+    };
+    let name = match &storage {
+        Storage::Static(label) => label.clone(),
+        _ => ident.to_string(),
+    };
+    declare_global(Scope {
+        kind: ScopeKind::Func,
+        ty: Some(Box::new(ty.clone())),
+        name: ident.to_string(),
+        str: None,
+        offset: None,
+        init: None,
+        storage,
+        attrs: attrs.clone(),
+        is_defined: true,
+    });
+    // A struct is returned by copying it to a buffer of the caller, whose
+    // address is passed before the other arguments and returned in a0
+    if ret_ty.kind == TypeKind::Struct {
+        let ptr_ty = create_pointer_type(ret_ty);
+        let ptr = local_var(alloc_local(&ptr_ty), ptr_ty);
+        unsafe { RET_PTR = Some(ptr.clone()) };
+        args.insert(0, ptr);
+    }
+    if args.len() > MAX_REG_ARGS {
+        return context("Too many parameters", fail)(i);
+    }
     let (mut t, _) = tag("{")(i)?;
 
     loop {
@@ -583,39 +1991,157 @@ fn parse_function(text: &str) -> IResult<&str, Func, VerboseError<&str>> {
             Func {
                 kind,
                 ty: Box::new(ty),
-                name: ident.to_string(),
+                name,
                 args,
                 nodes,
-                local_offset: LOCAL_OFFSET,
-                is_naked,
+                // Keep sp word aligned
+                local_offset: align_to(LOCAL_OFFSET.max(MAX_LOCAL_OFFSET), 2),
+                attrs,
             },
         ))
     }
 }
 
+// Check a function declaration against an earlier one of the same name,
+// which is returned
+fn check_function<'a>(
+    text: &'a str,
+    ident: &str,
+    ty: &Type,
+) -> IResult<&'a str, Option<Scope>, VerboseError<&'a str>> {
+    let prev = unsafe {
+        GLOBALS
+            .iter()
+            .find(|g| g.kind == ScopeKind::Func && g.name == ident)
+            .cloned()
+    };
+    if let Some(prev) = &prev {
+        if !prev.ty.clone().unwrap().is_compatible(ty) {
+            return context("Conflicting types for function", fail)(text);
+        }
+    }
+    Ok((text, prev))
+}
+
+// Function declared by a prototype. A declaration after the first one adds
+// nothing, so it is returned as extern and dropped by declare_global.
+fn new_function_decl<'a>(
+    text: &'a str,
+    ident: &str,
+    ty: Type,
+    storage: Option<&str>,
+    attrs: Attrs,
+) -> IResult<&'a str, Scope, VerboseError<&'a str>> {
+    let (i, prev) = check_function(text, ident, &ty)?;
+    let storage = match storage {
+        Some("static") if prev.is_none() => new_storage(storage, ident),
+        _ => Storage::Extern,
+    };
+    Ok((
+        i,
+        Scope {
+            kind: ScopeKind::Func,
+            ty: Some(Box::new(ty)),
+            name: ident.to_string(),
+            str: None,
+            offset: None,
+            init: None,
+            storage,
+            attrs,
+            is_defined: false,
+        },
+    ))
+}
+
+// Open a block scope. Returns the state that leave_block restores.
+fn enter_block() -> (usize, u16) {
+    unsafe {
+        let prev = (BLOCK_START, LOCAL_OFFSET);
+        BLOCK_START = LOCALS.len();
+        prev
+    }
+}
+
+// Close a block scope. Its stack slots are reused by the following blocks.
+fn leave_block((block_start, local_offset): (usize, u16)) {
+    unsafe {
+        MAX_LOCAL_OFFSET = MAX_LOCAL_OFFSET.max(LOCAL_OFFSET);
+        LOCALS.truncate(BLOCK_START);
+        BLOCK_START = block_start;
+        LOCAL_OFFSET = local_offset;
+    }
+}
+
+// Check a new local declaration against the ones already visible.
+// Tags and ordinary identifiers live in separate name spaces, and an
+// ordinary identifier is declared only once in a block.
+fn check_local<'a>(
+    text: &'a str,
+    ident: &str,
+    kind: ScopeKind,
+) -> IResult<&'a str, (), VerboseError<&'a str>> {
+    let same_space = |scope: &&Scope| {
+        scope.name == ident
+            && match kind {
+                ScopeKind::Tag => scope.kind == ScopeKind::Tag,
+                _ => matches!(
+                    scope.kind,
+                    ScopeKind::Var | ScopeKind::Typedef | ScopeKind::Enum
+                ),
+            }
+    };
+    unsafe {
+        if LOCALS[BLOCK_START..].iter().any(|s| same_space(&s)) {
+            return context("Redefinition in the same scope", fail)(text);
+        }
+        if LOCALS[..BLOCK_START].iter().any(|s| same_space(&s))
+            || GLOBALS.iter().any(|s| same_space(&s))
+        {
+            warn(
+                text,
+                WarningKind::Shadow,
+                format!("declaration of '{}' shadows a previous declaration", ident),
+            );
+        }
+    }
+    Ok((text, ()))
+}
+
 // func_param = declspec declarator
 fn parse_funcparam(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
     let (i, ty) = parse_declspec(text)?;
     let (i, _) = multispace0(i)?;
     let (i, (ident, ty)) = parse_declarator(i, ty)?;
+    let ty = param_type(ty);
+    check_local(i, ident, ScopeKind::Var)?;
+    let offset = alloc_local(&ty);
     unsafe {
-        LOCAL_OFFSET += ty.size;
         let lvar = Scope {
             kind: ScopeKind::Var,
             ty: Some(Box::new(ty.clone())),
             name: ident.to_string(),
-            offset: Some(LOCAL_OFFSET),
+            offset: Some(offset),
             str: None,
+            init: None,
+            storage: Storage::Default,
+            attrs: Attrs::default(),
+            is_defined: false,
         };
         LOCALS.push(lvar);
     }
-    Ok((
-        i,
-        Node {
-            kind: NodeKind::Null,
-            ty: Some(Box::new(ty)),
-        },
-    ))
+    Ok((i, local_var(offset, ty)))
+}
+
+// Unnamed local variable, e.g. a temporary
+fn local_var(offset: u16, ty: Type) -> Node {
+    Node {
+        kind: NodeKind::Var(Var {
+            name: String::new(),
+            offset,
+            is_global: false,
+        }),
+        ty: Some(Box::new(ty)),
+    }
 }
 
 // stmt = expr ";"
@@ -627,7 +2153,7 @@ fn parse_funcparam(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
 //       | "break" ";"
 //       | "continue" ";"
 //       | declaration ";"
-//       | "__asm__" "(" assembler ")" ;"
+//       | "__asm__" asm
 fn parse_stmt(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
     let (i, s) = opt(alt((
         tag("return"),
@@ -646,6 +2172,9 @@ fn parse_stmt(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
                 let (i, node) = parse_expr(i)?;
                 let (i, _) = multispace0(i)?;
                 let (i, _) = tag(";")(i)?;
+                if let Some(ptr) = unsafe { RET_PTR.clone() } {
+                    return new_struct_return(i, ptr, node);
+                }
                 Ok((
                     i,
                     Node {
@@ -736,13 +2265,18 @@ fn parse_stmt(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
                 let mut init: Option<Box<Node>> = None;
                 let mut cond: Option<Box<Node>> = None;
                 let mut inc: Option<Box<Node>> = None;
+                let block = enter_block();
 
                 let (i, _) = multispace0(i)?;
                 let (i, _) = tag("(")(i)?;
                 let (i, _) = multispace0(i)?;
                 let (mut i, s) = opt(tag(";"))(i)?;
                 if s.is_none() {
-                    let (j, expr) = alt((parse_declaration, parse_expr))(i)?;
+                    let (j, expr) = if is_typename(i) {
+                        parse_declaration(i)?
+                    } else {
+                        parse_expr(i)?
+                    };
                     init = Some(Box::new(expr));
                     let (j, _) = multispace0(j)?;
                     let (j, _) = tag(";")(j)?;
@@ -770,6 +2304,7 @@ fn parse_stmt(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
                 let (i, stmt) = parse_stmt(i)?;
                 let then = Some(Box::new(stmt));
                 SCOPE_CTR_LABEL = prev_label;
+                leave_block(block);
 
                 Ok((
                     i,
@@ -814,10 +2349,12 @@ fn parse_stmt(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
             "{" => {
                 let mut t = i;
                 let mut body: Vec<Node> = Vec::new();
+                let block = enter_block();
                 loop {
                     let (i, _) = parse_space_or_comment(t)?;
                     let (i, s) = opt(tag("}"))(i)?;
                     if s.is_some() {
+                        leave_block(block);
                         return Ok((
                             i,
                             Node {
@@ -831,47 +2368,65 @@ fn parse_stmt(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
                     t = i;
                 }
             }
-            "__asm__" => {
-                let (i, _) = tag("(")(i)?;
-                let (i, _) = tag("\"")(i)?;
-                let (i, asm) = take_until("\"")(i)?;
-                let (i, _) = tag("\"")(i)?;
-                let (i, _) = tag(")")(i)?;
-                let (i, _) = multispace0(i)?;
-                let (i, _) = tag(";")(i)?;
-                Ok((
-                    i,
-                    Node {
-                        kind: NodeKind::Asm(Asm {
-                            asm: asm.to_string(),
-                        }),
-                        ty: None,
-                    },
-                ))
-            }
+            "__asm__" => parse_asm(i),
 
             _ => {
                 unreachable!()
             }
         }
-    } else {
-        let (i, node) = opt(parse_declaration)(text)?;
-        if let Some(node) = node {
-            let (i, _) = multispace0(i)?;
-            let (i, _) = tag(";")(i)?;
-            Ok((i, node))
-        } else {
-            let (i, node) = parse_expr(i)?;
-            let (i, _) = multispace0(i)?;
-            let (i, _) = tag(";")(i)?;
-            Ok((i, node))
-        }
+    } else {
+        let (i, node) = if is_static_assert(text) {
+            let (i, _) = parse_static_assert(text)?;
+            (
+                i,
+                Node {
+                    kind: NodeKind::Null,
+                    ty: None,
+                },
+            )
+        } else if is_typename(text) {
+            parse_declaration(text)?
+        } else {
+            parse_expr(text)?
+        };
+        let (i, _) = multispace0(i)?;
+        let (i, _) = tag(";")(i)?;
+        Ok((i, node))
     }
 }
 
-// declaration = declspec (ident ("[" num "]")* | ("=" expr ))?
+// Copy a returned struct to the buffer of the caller and return its address
+fn new_struct_return(text: &str, ptr: Node, node: Node) -> IResult<&str, Node, VerboseError<&str>> {
+    let ret_ty = *ptr.ty.clone().unwrap().ptr_to.unwrap();
+    let buf = Node {
+        kind: NodeKind::Deref(Unary {
+            unary: Box::new(ptr.clone()),
+        }),
+        ty: Some(Box::new(ret_ty.clone())),
+    };
+    let (i, copy) = new_assign(text, buf, node)?;
+    let ret = Node {
+        kind: NodeKind::Return(Return {
+            expr: Box::new(ptr),
+        }),
+        ty: None,
+    };
+    Ok((
+        i,
+        Node {
+            kind: NodeKind::Block(Block {
+                body: vec![copy, ret],
+            }),
+            ty: None,
+        },
+    ))
+}
+
+// declaration = storage_class declspec (ident ("[" num "]")* | ("=" expr ))?
 fn parse_declaration(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
-    let (i, ty) = parse_declspec(text)?;
+    let (i, attrs) = parse_attributes(text)?;
+    let (i, storage) = parse_storage(i)?;
+    let (i, ty) = parse_declspec(i)?;
     let (i, _) = multispace0(i)?;
 
     // if declaration only
@@ -887,14 +2442,53 @@ fn parse_declaration(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
     }
 
     let (i, (ident, ty)) = parse_declarator(i, ty)?;
+    let (i, _) = multispace0(i)?;
+    let (i, post) = parse_attributes(i)?;
+    let attrs = attrs.merge(post);
+    // A function declared in a block is still a global
+    if ty.is_function() {
+        let (i, func) = new_function_decl(i, ident, ty, storage, attrs)?;
+        declare_global(func);
+        return Ok((
+            i,
+            Node {
+                kind: NodeKind::Null,
+                ty: None,
+            },
+        ));
+    }
+    check_local(i, ident, ScopeKind::Var)?;
+    let (i, _) = multispace0(i)?;
+    if storage.is_some() {
+        return parse_local_static(i, ident, ty, storage, attrs);
+    }
+    if ty.is_incomplete() {
+        return context("Variable has incomplete type", fail)(i);
+    }
+    check_stack_align(i, &aligned_type(&ty, &attrs))?;
+    let (_, s) = opt(tag("="))(i)?;
+    if s.is_some() && (ty.kind == TypeKind::Array || ty.kind == TypeKind::Struct) {
+        let (i, _) = tag("=")(i)?;
+        let (i, _) = multispace0(i)?;
+        let (i, (ty, inits)) = parse_initializer(i, &ty)?;
+        return Ok((i, new_local_init(ident, ty, inits, attrs)));
+    }
+    if ty.kind == TypeKind::Array && ty.size == 0 {
+        return context("Array size missing", fail)(i);
+    }
+
+    alloc_local(&aligned_type(&ty, &attrs));
     unsafe {
-        LOCAL_OFFSET += ty.size;
         let lvar = Scope {
             kind: ScopeKind::Var,
             ty: Some(Box::new(ty.clone())),
             name: ident.to_string(),
             offset: Some(LOCAL_OFFSET),
             str: None,
+            init: None,
+            storage: Storage::Default,
+            attrs,
+            is_defined: false,
         };
         LOCALS.push(lvar);
     }
@@ -915,6 +2509,8 @@ fn parse_declaration(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
 
             let (i, _) = multispace0(i)?;
             let (i, node) = parse_expr(i)?;
+            check_qualifiers(i, &ty, &node);
+            check_pointer_types(i, &ty, &node);
 
             Ok((
                 i,
@@ -938,6 +2534,321 @@ fn parse_declaration(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
     }
 }
 
+// Local static or extern variable, which lives in data memory
+fn parse_local_static<'a>(
+    text: &'a str,
+    ident: &str,
+    ty: Type,
+    storage: Option<&str>,
+    attrs: Attrs,
+) -> IResult<&'a str, Node, VerboseError<&'a str>> {
+    let (i, (ty, init)) = parse_opt_global_init(text, ty, storage)?;
+    if storage == Some("extern") && init.is_some() {
+        return context("Initializer on local extern", fail)(i);
+    }
+    let storage = new_storage(storage, ident);
+    let name = match &storage {
+        Storage::Static(label) => label.clone(),
+        _ => {
+            check_global(i, ident, &ty, &storage)?;
+            ident.to_string()
+        }
+    };
+    let scope = Scope {
+        kind: ScopeKind::Var,
+        ty: Some(Box::new(ty)),
+        name: ident.to_string(),
+        str: None,
+        offset: None,
+        init: None,
+        storage,
+        attrs,
+        is_defined: false,
+    };
+    declare_global(Scope {
+        name,
+        init,
+        ..scope.clone()
+    });
+    unsafe { LOCALS.push(scope) };
+    Ok((
+        i,
+        Node {
+            kind: NodeKind::Null,
+            ty: None,
+        },
+    ))
+}
+
+// Declare a local aggregate and assign the initializer items to it
+fn new_local_init(ident: &str, ty: Type, inits: Vec<InitItem>, attrs: Attrs) -> Node {
+    let offset = alloc_local(&aligned_type(&ty, &attrs));
+    unsafe {
+        LOCALS.push(Scope {
+            kind: ScopeKind::Var,
+            ty: Some(Box::new(ty.clone())),
+            name: ident.to_string(),
+            offset: Some(offset),
+            str: None,
+            init: None,
+            storage: Storage::Default,
+            attrs,
+            is_defined: false,
+        });
+    }
+    let var = |var_offset: u16, ty: Type| Node {
+        kind: NodeKind::Var(Var {
+            name: ident.to_string(),
+            offset: var_offset,
+            is_global: false,
+        }),
+        ty: Some(Box::new(ty)),
+    };
+
+    // Zero-fill unless every byte is initialized. Bit-fields are written by
+    // read-modify-write, so their storage units are always cleared first.
+    let mut initialized = vec![false; ty.size as usize];
+    for (member, _) in inits.iter().filter(|(member, _)| !member.is_bitfield()) {
+        for b in member.offset..member.offset + member.ty.size {
+            initialized[b as usize] = true;
+        }
+    }
+    let mut body = Vec::new();
+    if initialized.contains(&false) {
+        body.push(Node {
+            kind: NodeKind::MemZero(MemZero {
+                lval: Box::new(var(offset, ty.clone())),
+                size: ty.size,
+            }),
+            ty: None,
+        });
+    }
+    for (member, node) in inits {
+        let mem_ty = *member.ty.clone();
+        let mut left = var(offset - member.offset, mem_ty.clone());
+        if member.is_bitfield() {
+            left = Node {
+                kind: NodeKind::MemAccess(MemAccess {
+                    unary: Box::new(left),
+                    member: Box::new(Member {
+                        offset: 0,
+                        ..member
+                    }),
+                }),
+                ty: Some(Box::new(mem_ty.clone())),
+            };
+        }
+        body.push(Node {
+            kind: NodeKind::Assign(Binary {
+                left: Box::new(left),
+                right: Box::new(node),
+            }),
+            ty: Some(Box::new(mem_ty)),
+        });
+    }
+    Node {
+        kind: NodeKind::Block(Block { body }),
+        ty: None,
+    }
+}
+
+// Registers an asm operand may be given, in the order they are handed out
+const ASM_REGS: [&str; 5] = ["a0", "a1", "a2", "t0", "t1"];
+
+// asm = ("volatile" | "__volatile__")? "(" str (":" operands (":" operands (":" clobbers)?)?)? ")" ";"
+// Every operand gets a register of its own that the clobbers don't name. An
+// asm is never removed or moved, so volatile makes no difference.
+fn parse_asm(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
+    let (i, _) = multispace0(text)?;
+    let (i, _) = opt(alt((tag("__volatile__"), tag("volatile"))))(i)?;
+    let (i, _) = multispace0(i)?;
+    let (i, _) = tag("(")(i)?;
+    let (i, _) = multispace0(i)?;
+    let (i, template) = parse_str_literal(i)?;
+    let template = template.trim_end_matches('\0');
+    let (i, _) = multispace0(i)?;
+    let (i, colon) = opt(tag(":"))(i)?;
+    let (i, (outputs, inputs, clobbers)) = if colon.is_some() {
+        let (i, _) = multispace0(i)?;
+        let (i, outputs) = parse_asm_operands(i)?;
+        let (i, _) = multispace0(i)?;
+        let (i, inputs) = opt(preceded(tag(":"), parse_asm_operands))(i)?;
+        let (i, _) = multispace0(i)?;
+        let (i, clobbers) = opt(preceded(
+            tag(":"),
+            separated_list0(
+                delimited(multispace0, tag(","), multispace0),
+                preceded(multispace0, parse_str_literal),
+            ),
+        ))(i)?;
+        let (i, _) = multispace0(i)?;
+        (
+            i,
+            (
+                outputs,
+                inputs.unwrap_or_default(),
+                clobbers.unwrap_or_default(),
+            ),
+        )
+    } else {
+        (i, (vec![], vec![], vec![]))
+    };
+    let (i, _) = tag(")")(i)?;
+    let (i, _) = multispace0(i)?;
+    let (i, _) = tag(";")(i)?;
+
+    // A basic asm is pasted as it is
+    if colon.is_none() {
+        return Ok((
+            i,
+            Node {
+                kind: NodeKind::Asm(Asm {
+                    asm: template.to_string(),
+                    inputs: vec![],
+                    outputs: vec![],
+                }),
+                ty: None,
+            },
+        ));
+    }
+
+    let mut free = ASM_REGS.to_vec();
+    for clobber in &clobbers {
+        match clobber.trim_end_matches('\0') {
+            "memory" | "cc" | "ra" => {}
+            reg if ASM_REGS.contains(&reg) => free.retain(|&r| r != reg),
+            _ => return context("Invalid register in clobber list", fail)(i),
+        }
+    }
+    let mut take_reg = || (!free.is_empty()).then(|| free.remove(0).to_string());
+
+    // Operands are numbered from the first output to the last input
+    let mut operands: Vec<(Option<&str>, String)> = Vec::new();
+    let mut asm_inputs = Vec::new();
+    let mut asm_outputs = Vec::new();
+    for (name, constraint, expr) in outputs {
+        if constraint != "=r" && constraint != "+r" {
+            return context("Invalid output constraint", fail)(i);
+        }
+        let ty = expr.ty.clone().unwrap();
+        let is_lval = matches!(
+            expr.kind,
+            NodeKind::Var(_) | NodeKind::Deref(_) | NodeKind::MemAccess(_)
+        );
+        if !is_lval || ty.is_const || !(ty.is_integer() || ty.kind == TypeKind::Pointer) {
+            return context("Invalid asm output", fail)(i);
+        }
+        let Some(reg) = take_reg() else {
+            return context("Too many asm operands", fail)(i);
+        };
+        if constraint == "+r" {
+            asm_inputs.push((reg.clone(), expr.clone()));
+        }
+        asm_outputs.push((reg.clone(), expr));
+        operands.push((name, reg));
+    }
+    for (name, constraint, expr) in inputs {
+        // e.g. a comparison has no type but is an int
+        if expr
+            .ty
+            .as_ref()
+            .is_some_and(|ty| !(ty.is_integer() || ty.is_pointer()))
+        {
+            return context("Invalid asm input", fail)(i);
+        }
+        let operand = match constraint.as_str() {
+            "r" => match take_reg() {
+                Some(reg) => {
+                    asm_inputs.push((reg.clone(), expr));
+                    reg
+                }
+                None => return context("Too many asm operands", fail)(i),
+            },
+            "i" | "n" => match eval(&expr) {
+                Some(val) => (val as i16).to_string(),
+                None => return context("Asm operand is not a constant", fail)(i),
+            },
+            // The same register as an output
+            n => match n.parse::<usize>().ok().and_then(|n| asm_outputs.get(n)) {
+                Some((reg, _)) => {
+                    let reg = reg.clone();
+                    asm_inputs.push((reg.clone(), expr));
+                    reg
+                }
+                None => return context("Invalid input constraint", fail)(i),
+            },
+        };
+        operands.push((name, operand));
+    }
+
+    let Some(asm) = substitute_asm(template, &operands) else {
+        return context("Invalid operand reference in asm", fail)(i);
+    };
+    Ok((
+        i,
+        Node {
+            kind: NodeKind::Asm(Asm {
+                asm,
+                inputs: asm_inputs,
+                outputs: asm_outputs,
+            }),
+            ty: None,
+        },
+    ))
+}
+
+// An operand of extended asm: name, constraint and expression
+type AsmOperand<'a> = (Option<&'a str>, String, Node);
+
+// operands = (("[" ident "]")? str "(" expr ")" ("," ...)*)?
+fn parse_asm_operands(text: &str) -> IResult<&str, Vec<AsmOperand<'_>>, VerboseError<&str>> {
+    separated_list0(delimited(multispace0, tag(","), multispace0), |i| {
+        let (i, _) = multispace0(i)?;
+        let (i, name) = opt(delimited(
+            tag("["),
+            delimited(multispace0, take_while1(is_ident), multispace0),
+            tag("]"),
+        ))(i)?;
+        let (i, _) = multispace0(i)?;
+        let (i, constraint) = parse_str_literal(i)?;
+        let (i, _) = multispace0(i)?;
+        let (i, expr) = delimited(
+            tag("("),
+            delimited(multispace0, parse_expr, multispace0),
+            tag(")"),
+        )(i)?;
+        let constraint = constraint.trim_end_matches('\0').to_string();
+        Ok((i, (name, constraint, expr)))
+    })(text)
+}
+
+// Replace %n and %[name] in an asm template by the operands, and %% by %
+fn substitute_asm(template: &str, operands: &[(Option<&str>, String)]) -> Option<String> {
+    let mut asm = String::new();
+    let mut rest = template;
+    while let Some(pos) = rest.find('%') {
+        asm.push_str(&rest[..pos]);
+        rest = &rest[pos + 1..];
+        if let Some(r) = rest.strip_prefix('%') {
+            asm.push('%');
+            rest = r;
+        } else if let Some(r) = rest.strip_prefix('[') {
+            let end = r.find(']')?;
+            let name = &r[..end];
+            let (_, operand) = operands.iter().find(|(n, _)| *n == Some(name))?;
+            asm.push_str(operand);
+            rest = &r[end + 1..];
+        } else {
+            let len = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+            let (_, operand) = operands.get(rest[..len].parse::<usize>().ok()?)?;
+            asm.push_str(operand);
+            rest = &rest[len..];
+        }
+    }
+    asm.push_str(rest);
+    Some(asm)
+}
+
 // expr = assign
 fn parse_expr(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
     parse_assign(text)
@@ -945,26 +2856,48 @@ fn parse_expr(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
 
 // assign = logor ("=" assign)?
 fn parse_assign(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
-    let (i, mut node) = parse_logor(text)?;
+    let (i, node) = parse_logor(text)?;
     let (i, _) = multispace0(i)?;
     let (i, s) = opt(tag("="))(i)?;
     if s.is_some() {
+        let left_ty = node.ty.clone().unwrap();
+        if left_ty.is_const || left_ty.kind == TypeKind::Array {
+            return context("Assignment to read-only location", fail)(i);
+        }
         let (i, _) = multispace0(i)?;
         let (i, right) = parse_assign(i)?;
-        let ty = node.ty.clone();
-        node = Node {
-            kind: NodeKind::Assign(Binary {
-                left: Box::new(node),
-                right: Box::new(right),
-            }),
-            ty,
-        };
-        Ok((i, node))
+        new_assign(i, node, right)
     } else {
         Ok((i, node))
     }
 }
 
+// A struct is assigned by copying it, so both sides must be the same struct
+fn new_assign(text: &str, left: Node, right: Node) -> IResult<&str, Node, VerboseError<&str>> {
+    let left_ty = left.ty.clone().unwrap();
+    let right_ty = right.ty.clone().unwrap();
+    if left_ty.is_incomplete() || right_ty.is_incomplete() {
+        return context("Assignment of incomplete type", fail)(text);
+    }
+    if (left_ty.kind == TypeKind::Struct || right_ty.kind == TypeKind::Struct)
+        && !left_ty.is_same_struct(&right_ty)
+    {
+        return context("Incompatible types in assignment", fail)(text);
+    }
+    check_qualifiers(text, &left_ty, &right);
+    check_pointer_types(text, &left_ty, &right);
+    Ok((
+        text,
+        Node {
+            kind: NodeKind::Assign(Binary {
+                left: Box::new(left),
+                right: Box::new(right),
+            }),
+            ty: Some(left_ty),
+        },
+    ))
+}
+
 // logor = logand ("||" logand)*
 fn parse_logor(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
     let (mut t, mut node) = parse_logand(text)?;
@@ -1034,12 +2967,13 @@ fn parse_bitor(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
             if s == "|" {
                 let (i, _) = multispace0(i)?;
                 let (i, right) = parse_bitxor(i)?;
+                let ty = arith_type(&node, &right);
                 node = Node {
                     kind: NodeKind::BitOr(Binary {
                         left: Box::new(node),
                         right: Box::new(right),
                     }),
-                    ty: Some(Box::new(create_int_type())),
+                    ty: Some(Box::new(ty.clone())),
                 };
                 t = i;
             } else {
@@ -1062,12 +2996,13 @@ fn parse_bitxor(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
         if s.is_some() {
             let (i, _) = multispace0(i)?;
             let (i, right) = parse_bitand(i)?;
+            let ty = arith_type(&node, &right);
             node = Node {
                 kind: NodeKind::BitXor(Binary {
                     left: Box::new(node),
                     right: Box::new(right),
                 }),
-                ty: Some(Box::new(create_int_type())),
+                ty: Some(Box::new(ty.clone())),
             };
             t = i;
         } else {
@@ -1087,12 +3022,13 @@ fn parse_bitand(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
             if s == "&" {
                 let (i, _) = multispace0(i)?;
                 let (i, right) = parse_equality(i)?;
+                let ty = arith_type(&node, &right);
                 node = Node {
                     kind: NodeKind::BitAnd(Binary {
                         left: Box::new(node),
                         right: Box::new(right),
                     }),
-                    ty: Some(Box::new(create_int_type())),
+                    ty: Some(Box::new(ty.clone())),
                 };
                 t = i;
             } else {
@@ -1116,6 +3052,7 @@ fn parse_equality(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
         if let Some(s) = s {
             let (i, _) = multispace0(i)?;
             let (i, right) = parse_relational(i)?;
+            check_pointer_cmp(i, &node, &right);
             if s == "==" {
                 node = Node {
                     kind: NodeKind::Eq(Binary {
@@ -1151,6 +3088,7 @@ fn parse_relational(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
         if let Some(s) = s {
             let (i, _) = multispace0(i)?;
             let (i, right) = parse_shift(i)?;
+            check_pointer_cmp(i, &node, &right);
             match s {
                 "<" => {
                     node = Node {
@@ -1210,6 +3148,7 @@ fn parse_shift(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
         if let Some(s) = s {
             let (i, _) = multispace0(i)?;
             let (i, right) = parse_shift(i)?;
+            let ty = arith_type(&node, &node);
             match s {
                 "<<" => {
                     node = Node {
@@ -1217,7 +3156,7 @@ fn parse_shift(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
                             left: Box::new(node),
                             right: Box::new(right),
                         }),
-                        ty: Some(Box::new(create_int_type())),
+                        ty: Some(Box::new(ty.clone())),
                     }
                 }
                 ">>" => {
@@ -1226,7 +3165,7 @@ fn parse_shift(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
                             left: Box::new(node),
                             right: Box::new(right),
                         }),
-                        ty: Some(Box::new(create_int_type())),
+                        ty: Some(Box::new(ty.clone())),
                     }
                 }
                 ">>>" => {
@@ -1235,7 +3174,7 @@ fn parse_shift(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
                             left: Box::new(node),
                             right: Box::new(right),
                         }),
-                        ty: Some(Box::new(create_int_type())),
+                        ty: Some(Box::new(ty.clone())),
                     }
                 }
 
@@ -1270,39 +3209,26 @@ fn parse_add(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
                         && (right.ty.clone().unwrap().kind == TypeKind::Int
                             || right.ty.clone().unwrap().kind == TypeKind::Char)
                     {
+                        let ty = arith_type(&node, &right);
                         node = Node {
                             kind: NodeKind::Add(Binary {
                                 left: Box::new(node),
                                 right: Box::new(right),
                             }),
-                            ty: Some(Box::new(create_int_type())),
+                            ty: Some(Box::new(ty)),
                         }
                     } else if (node.ty.clone().unwrap().kind == TypeKind::Pointer
                         || node.ty.clone().unwrap().kind == TypeKind::Array)
                         && (right.ty.clone().unwrap().kind == TypeKind::Int
                             || right.ty.clone().unwrap().kind == TypeKind::Char)
                     {
-                        let ty = node.ty.clone();
-                        node = Node {
-                            kind: NodeKind::PtrAdd(Binary {
-                                left: Box::new(node),
-                                right: Box::new(right),
-                            }),
-                            ty,
-                        }
+                        node = new_ptr_add(node, right, false);
                     } else if (node.ty.clone().unwrap().kind == TypeKind::Int
                         || node.ty.clone().unwrap().kind == TypeKind::Char)
                         && (right.ty.clone().unwrap().kind == TypeKind::Pointer
                             || right.ty.clone().unwrap().kind == TypeKind::Array)
                     {
-                        let ty = right.ty.clone();
-                        node = Node {
-                            kind: NodeKind::PtrAdd(Binary {
-                                left: Box::new(right),
-                                right: Box::new(node),
-                            }),
-                            ty,
-                        }
+                        node = new_ptr_add(right, node, false);
                     } else {
                         return context("Invalid operands", fail)(i);
                     }
@@ -1316,24 +3242,43 @@ fn parse_add(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
                         && (right.ty.clone().unwrap().kind == TypeKind::Int
                             || right.ty.clone().unwrap().kind == TypeKind::Char)
                     {
+                        let ty = arith_type(&node, &right);
                         node = Node {
                             kind: NodeKind::Sub(Binary {
                                 left: Box::new(node),
                                 right: Box::new(right),
                             }),
-                            ty: Some(Box::new(create_int_type())),
+                            ty: Some(Box::new(ty)),
                         }
-                    } else if node.ty.clone().unwrap().kind == TypeKind::Pointer
+                    } else if node.ty.clone().unwrap().is_pointer()
                         && (right.ty.clone().unwrap().kind == TypeKind::Int
                             || right.ty.clone().unwrap().kind == TypeKind::Char)
                     {
-                        let ty = node.ty.clone();
+                        node = new_ptr_add(node, right, true);
+                    } else if node.ty.clone().unwrap().is_pointer()
+                        && right.ty.clone().unwrap().is_pointer()
+                    {
+                        // The number of elements between two pointers
+                        let elem = complete_type(*node.ty.clone().unwrap().ptr_to.unwrap());
+                        let other = complete_type(*right.ty.clone().unwrap().ptr_to.unwrap());
+                        if !elem.is_compatible_pointee(&other) || elem.size == 0 {
+                            return context("Invalid operands", fail)(i);
+                        }
                         node = Node {
-                            kind: NodeKind::PtrSub(Binary {
+                            kind: NodeKind::Sub(Binary {
                                 left: Box::new(node),
                                 right: Box::new(right),
                             }),
-                            ty,
+                            ty: Some(Box::new(create_int_type())),
+                        };
+                        if elem.size > 1 {
+                            node = Node {
+                                kind: NodeKind::Div(Binary {
+                                    left: Box::new(node),
+                                    right: Box::new(new_num(elem.size as i32)),
+                                }),
+                                ty: Some(Box::new(create_int_type())),
+                            };
                         }
                     } else {
                         return context("Invalid operands", fail)(i);
@@ -1350,16 +3295,17 @@ fn parse_add(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
     }
 }
 
-// mul = unary ("*" unary | "/" unary | "%" unary)*
+// mul = cast ("*" cast | "/" cast | "%" cast)*
 fn parse_mul(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
-    let (mut t, mut node) = parse_unary(text)?;
+    let (mut t, mut node) = parse_cast(text)?;
 
     loop {
         let (i, _) = multispace0(t)?;
         let (i, s) = opt(alt((tag("*"), tag("/"), tag("%"))))(i)?;
         if let Some(s) = s {
             let (i, _) = multispace0(i)?;
-            let (i, right) = parse_unary(i)?;
+            let (i, right) = parse_cast(i)?;
+            let ty = arith_type(&node, &right);
             match s {
                 "*" => {
                     node = Node {
@@ -1367,7 +3313,7 @@ fn parse_mul(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
                             left: Box::new(node),
                             right: Box::new(right),
                         }),
-                        ty: Some(Box::new(create_int_type())),
+                        ty: Some(Box::new(ty.clone())),
                     }
                 }
                 "/" => {
@@ -1376,7 +3322,7 @@ fn parse_mul(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
                             left: Box::new(node),
                             right: Box::new(right),
                         }),
-                        ty: Some(Box::new(create_int_type())),
+                        ty: Some(Box::new(ty.clone())),
                     }
                 }
                 "%" => {
@@ -1385,7 +3331,7 @@ fn parse_mul(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
                             left: Box::new(node),
                             right: Box::new(right),
                         }),
-                        ty: Some(Box::new(create_int_type())),
+                        ty: Some(Box::new(ty.clone())),
                     }
                 }
                 _ => {
@@ -1399,12 +3345,51 @@ fn parse_mul(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
     }
 }
 
-// unary = ("+" | "-")? primary ("[" expr "]" | "." ident | "->" ident)*
-//       | "*" unary
-//       | "&" unary
-//       | "!" unary
-//       | "~" unary
-//       | "sizeof" unary
+// cast = "(" type_name ")" cast | unary
+fn parse_cast(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
+    let (i, s) = opt(tag("("))(text)?;
+    if s.is_some() {
+        let (i, _) = multispace0(i)?;
+        if is_typename(i) {
+            let (i, ty) = parse_typename(i)?;
+            let (i, _) = multispace0(i)?;
+            let (i, _) = tag(")")(i)?;
+            let (i, _) = multispace0(i)?;
+            let (i, node) = parse_cast(i)?;
+            return new_cast(i, node, ty);
+        }
+    }
+    parse_unary(text)
+}
+
+// Type-checked conversion of node to ty
+fn new_cast(i: &str, node: Node, ty: Type) -> IResult<&str, Node, VerboseError<&str>> {
+    let from = match node.ty.clone() {
+        Some(from) => from,
+        None => return context("Invalid cast", fail)(i),
+    };
+    let is_scalar = |ty: &Type| ty.is_integer() || ty.is_pointer() || ty.kind == TypeKind::Func;
+    if !is_scalar(&from) || !is_scalar(&ty) || ty.kind == TypeKind::Array {
+        return context("Invalid cast", fail)(i);
+    }
+    Ok((
+        i,
+        Node {
+            kind: NodeKind::Cast(Unary {
+                unary: Box::new(node),
+            }),
+            ty: Some(Box::new(ty)),
+        },
+    ))
+}
+
+// unary = primary ("[" expr "]" | "." ident | "->" ident)*
+//       | ("+" | "-") cast
+//       | "*" cast
+//       | "&" cast
+//       | "!" cast
+//       | "~" cast
+//       | ("sizeof" | "_Alignof" | "alignof") ("(" type_name ")" | unary)
 fn parse_unary(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
     let (i, s) = opt(alt((
         tag("+"),
@@ -1413,31 +3398,70 @@ fn parse_unary(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
         tag("*"),
         tag("!"),
         tag("~"),
-        tag("sizeof"),
+        // Keywords must not be the start of a longer identifier
+        verify(take_while1(is_ident), |s: &str| {
+            matches!(
+                s,
+                "sizeof"
+                    | "_Alignof"
+                    | "alignof"
+                    | "offsetof"
+                    | "va_start"
+                    | "va_arg"
+                    | "va_copy"
+                    | "va_end"
+            )
+        }),
     )))(text)?;
 
     if let Some(s) = s {
+        let (i, _) = multispace0(i)?;
         match s {
-            "+" => Ok(parse_primary(i)?),
+            "+" => {
+                let (i, unary) = parse_cast(i)?;
+                if !unary.ty.clone().unwrap().is_integer() {
+                    return context("Invalid operand to unary +", fail)(i);
+                }
+                Ok((i, unary))
+            }
             "-" => {
-                let left = Node {
-                    kind: NodeKind::Num(Num { val: 0 }),
-                    ty: Some(Box::new(create_int_type())),
-                };
-                let (i, right) = parse_primary(i)?;
+                let (i, unary) = parse_cast(i)?;
+                if !unary.ty.clone().unwrap().is_integer() {
+                    return context("Invalid operand to unary -", fail)(i);
+                }
+                let ty = arith_type(&unary, &unary);
+                // Fold negative constants into the literal itself
+                if let NodeKind::Num(n) = &unary.kind {
+                    let val = if ty.is_uint() {
+                        -n.val & 0xffff
+                    } else {
+                        -n.val
+                    };
+                    return Ok((
+                        i,
+                        Node {
+                            kind: NodeKind::Num(Num { val }),
+                            ty: Some(Box::new(ty)),
+                        },
+                    ));
+                }
                 Ok((
                     i,
                     Node {
-                        kind: NodeKind::Sub(Binary {
-                            left: Box::new(left),
-                            right: Box::new(right),
+                        kind: NodeKind::Neg(Unary {
+                            unary: Box::new(unary),
                         }),
-                        ty: Some(Box::new(create_int_type())),
+                        ty: Some(Box::new(ty)),
                     },
                 ))
             }
             "&" => {
-                let (i, unary) = parse_unary(i)?;
+                let (i, unary) = parse_cast(i)?;
+                if let NodeKind::MemAccess(n) = &unary.kind {
+                    if n.member.is_bitfield() {
+                        return context("Cannot take address of bit-field", fail)(i);
+                    }
+                }
                 let ty = unary.ty.clone();
                 Ok((
                     i,
@@ -1449,68 +3473,140 @@ fn parse_unary(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
                             kind: TypeKind::Pointer,
                             ptr_to: ty,
                             size: 2,
+                            align: 2,
                             members: None,
+                            is_unsigned: false,
+                            is_const: false,
+                            is_volatile: false,
+                            params: None,
+                            is_variadic: false,
+                            tag: None,
+                            is_union: false,
                         })),
                     },
                 ))
             }
             "*" => {
-                let (i, unary) = parse_unary(i)?;
+                let (i, unary) = parse_cast(i)?;
                 if unary.ty.clone().unwrap().kind != TypeKind::Pointer
                     && unary.ty.clone().unwrap().kind != TypeKind::Array
                 {
                     context("Invalid pointer dereference", fail)(i)
                 } else {
-                    let ty = unary.ty.clone();
-                    Ok((
-                        i,
-                        Node {
-                            kind: NodeKind::Deref(Unary {
-                                unary: Box::new(unary),
-                            }),
-                            ty: ty.unwrap().ptr_to,
-                        },
-                    ))
+                    Ok((i, new_deref(unary)))
                 }
             }
-            "!" => {
-                let (i, unary) = parse_unary(i)?;
+            "!" => {
+                let (i, unary) = parse_cast(i)?;
+                Ok((
+                    i,
+                    Node {
+                        kind: NodeKind::Not(Unary {
+                            unary: Box::new(unary),
+                        }),
+                        ty: Some(Box::new(create_int_type())),
+                    },
+                ))
+            }
+            "~" => {
+                let (i, unary) = parse_cast(i)?;
+                let ty = arith_type(&unary, &unary);
+                Ok((
+                    i,
+                    Node {
+                        kind: NodeKind::BitNot(Unary {
+                            unary: Box::new(unary),
+                        }),
+                        ty: Some(Box::new(ty)),
+                    },
+                ))
+            }
+            "offsetof" => {
+                let (i, _) = tag("(")(i)?;
+                let (i, _) = multispace0(i)?;
+                let (i, ty) = parse_typename(i)?;
+                let (i, _) = multispace0(i)?;
+                let (i, _) = tag(",")(i)?;
+                let (i, _) = multispace0(i)?;
+                let (i, val) = parse_member_offset(i, ty)?;
+                let (i, _) = multispace0(i)?;
+                let (i, _) = tag(")")(i)?;
+                Ok((i, new_num(val as i32)))
+            }
+            // The variable arguments are read from the stack frame of the caller
+            "va_start" => {
+                if unsafe { !IS_VARIADIC } {
+                    return context("va_start used in function with fixed arguments", fail)(i);
+                }
+                let (i, _) = delimited(multispace0, tag("("), multispace0)(i)?;
+                let (i, ap) = parse_va_list(i)?;
+                let (i, _) = delimited(multispace0, tag(","), multispace0)(i)?;
+                let (i, _) = take_while1(is_ident)(i)?;
+                let (i, _) = preceded(multispace0, tag(")"))(i)?;
                 Ok((
                     i,
                     Node {
-                        kind: NodeKind::Not(Unary {
-                            unary: Box::new(unary),
+                        kind: NodeKind::VaStart(Unary {
+                            unary: Box::new(ap),
                         }),
-                        ty: Some(Box::new(create_int_type())),
+                        ty: None,
                     },
                 ))
             }
-            "~" => {
-                let (i, unary) = parse_unary(i)?;
+            "va_arg" => {
+                let (i, _) = delimited(multispace0, tag("("), multispace0)(i)?;
+                let (i, ap) = parse_va_list(i)?;
+                let (i, _) = delimited(multispace0, tag(","), multispace0)(i)?;
+                let (i, ty) = parse_typename(i)?;
+                if !ty.is_integer() && ty.kind != TypeKind::Pointer {
+                    return context("Invalid type for va_arg", fail)(i);
+                }
+                let (i, _) = preceded(multispace0, tag(")"))(i)?;
                 Ok((
                     i,
                     Node {
-                        kind: NodeKind::BitNot(Unary {
-                            unary: Box::new(unary),
+                        kind: NodeKind::VaArg(Unary {
+                            unary: Box::new(ap),
                         }),
-                        ty: Some(Box::new(create_int_type())),
+                        ty: Some(Box::new(ty)),
+                    },
+                ))
+            }
+            "va_copy" => {
+                let (i, _) = delimited(multispace0, tag("("), multispace0)(i)?;
+                let (i, dest) = parse_va_list(i)?;
+                let (i, _) = delimited(multispace0, tag(","), multispace0)(i)?;
+                let (i, src) = parse_va_list(i)?;
+                let (i, _) = preceded(multispace0, tag(")"))(i)?;
+                new_assign(i, dest, src)
+            }
+            "va_end" => {
+                let (i, _) = delimited(multispace0, tag("("), multispace0)(i)?;
+                let (i, _) = parse_va_list(i)?;
+                let (i, _) = preceded(multispace0, tag(")"))(i)?;
+                Ok((
+                    i,
+                    Node {
+                        kind: NodeKind::Null,
+                        ty: None,
                     },
                 ))
             }
-            "sizeof" => {
+            "sizeof" | "_Alignof" | "alignof" => {
                 let (i, _) = multispace0(i)?;
-                let (i, node) = parse_unary(i)?;
-                let ty = node.ty;
-                match ty {
-                    Some(ty) => Ok((
-                        i,
-                        Node {
-                            kind: NodeKind::Num(Num { val: ty.size }),
-                            ty: Some(Box::new(create_int_type())),
-                        },
-                    )),
-                    None => context("Unable to calculate", fail)(i),
+                let (i, ty) = parse_sizeof_operand(i)?;
+                let ty = complete_type(ty);
+                if ty.is_incomplete() {
+                    return context("Invalid application of sizeof to incomplete type", fail)(i);
                 }
+                let val = if s == "sizeof" { ty.size } else { ty.align } as i32;
+                Ok((
+                    i,
+                    Node {
+                        kind: NodeKind::Num(Num { val }),
+                        ty: Some(Box::new(create_int_type())),
+                    },
+                ))
             }
             _ => {
                 unreachable!()
@@ -1520,85 +3616,47 @@ fn parse_unary(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
         let (mut t, mut node) = parse_primary(i)?;
         loop {
             let (i, _) = multispace0(t)?;
-            let (i, s) = opt(alt((tag("."), tag("["), tag("->"))))(i)?;
+            let (i, s) = opt(alt((tag("."), tag("["), tag("->"), tag("("))))(i)?;
             if let Some(s) = s {
                 match s {
+                    "(" => {
+                        let ty = node.ty.clone().unwrap();
+                        let func_ty = match ty.kind {
+                            TypeKind::Func => ty.clone(),
+                            TypeKind::Pointer
+                                if ty.ptr_to.as_ref().unwrap().kind == TypeKind::Func =>
+                            {
+                                ty.ptr_to.unwrap()
+                            }
+                            _ => return context("Called object is not a function", fail)(i),
+                        };
+                        // (*p)(...) calls the address in p
+                        let callee = match node.kind {
+                            NodeKind::Deref(n) if ty.kind == TypeKind::Func => *n.unary,
+                            _ => node,
+                        };
+                        let (i, args) = parse_args(i)?;
+                        (t, node) = new_call(i, String::new(), Some(callee), Some(&func_ty), args)?;
+                    }
                     "[" => {
                         let (i, _) = multispace0(i)?;
                         let (i, right) = parse_expr(i)?;
-                        let ty = node.clone().ty;
-                        node = Node {
-                            kind: NodeKind::PtrAdd(Binary {
-                                left: Box::new(node),
-                                right: Box::new(right),
-                            }),
-                            ty,
-                        };
-                        let ty = node.clone().ty;
-                        node = Node {
-                            kind: NodeKind::Deref(Unary {
-                                unary: Box::new(node),
-                            }),
-                            ty: ty.unwrap().ptr_to,
-                        };
+                        node = new_deref(new_ptr_add(node, right, false));
                         let (i, _) = multispace0(i)?;
                         let (i, _) = tag("]")(i)?;
                         t = i;
                     }
                     "." => {
-                        let ty = node.clone().ty.unwrap();
-                        if ty.kind != TypeKind::Struct {
-                            return context("Not a struct", fail)(i);
-                        }
                         let (i, ident) = take_while1(is_ident)(i)?;
-                        if let Some(mem) = ty
-                            .members
-                            .unwrap()
-                            .iter()
-                            .find(|&member| member.name == ident)
-                        {
-                            node = Node {
-                                kind: NodeKind::MemAccess(MemAccess {
-                                    unary: Box::new(node),
-                                    member: Box::new(mem.clone()),
-                                }),
-                                ty: Some(mem.ty.clone()),
-                            }
-                        } else {
-                            return context("Not such member", fail)(i);
-                        }
-                        t = i;
+                        (t, node) = new_mem_access(i, node, ident)?;
                     }
                     "->" => {
                         let ty = node.clone().ty.unwrap();
-                        node = Node {
-                            kind: NodeKind::Deref(Unary {
-                                unary: Box::new(node),
-                            }),
-                            ty: ty.clone().ptr_to,
-                        };
-                        let ty = ty.ptr_to.unwrap();
-                        if ty.kind != TypeKind::Struct {
-                            return context("Not a struct", fail)(i);
+                        if !ty.is_pointer() {
+                            return context("Not a pointer", fail)(i);
                         }
                         let (i, ident) = take_while1(is_ident)(i)?;
-                        if let Some(mem) = ty
-                            .members
-                            .unwrap()
-                            .iter()
-                            .find(|&member| member.name == ident)
-                        {
-                            node = Node {
-                                kind: NodeKind::MemAccess(MemAccess {
-                                    unary: Box::new(node),
-                                    member: Box::new(mem.clone()),
-                                }),
-                                ty: Some(mem.ty.clone()),
-                            }
-                        } else {
-                            return context("Not such member", fail)(i);
-                        }
-                        t = i;
+                        (t, node) = new_mem_access(i, new_deref(node), ident)?;
                     }
                     _ => {
                         unreachable!()
@@ -1611,7 +3669,137 @@ fn parse_unary(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
     }
 }
 
-// primary = num | str |  ident ( "(" args ")" )? | "(" expr ")"
+// *ptr. A pointer to an incomplete struct may point to a complete one by now.
+fn new_deref(ptr: Node) -> Node {
+    let ty = complete_type(*ptr.ty.clone().unwrap().ptr_to.unwrap());
+    Node {
+        kind: NodeKind::Deref(Unary {
+            unary: Box::new(ptr),
+        }),
+        ty: Some(Box::new(ty)),
+    }
+}
+
+// node.ident
+fn new_mem_access<'a>(
+    text: &'a str,
+    node: Node,
+    ident: &str,
+) -> IResult<&'a str, Node, VerboseError<&'a str>> {
+    let ty = node.ty.clone().unwrap();
+    if ty.kind != TypeKind::Struct {
+        return context("Not a struct", fail)(text);
+    }
+    if ty.is_incomplete() {
+        return context("Incomplete struct type", fail)(text);
+    }
+    let Some(mut mem) = ty.find_member(ident) else {
+        return context("Not such member", fail)(text);
+    };
+    let mem_ty = mem.ty.qualified_by(&ty);
+    // a.b.c is a single member access at the sum of the offsets
+    let node = match node.kind {
+        NodeKind::MemAccess(inner) => {
+            mem.offset += inner.member.offset;
+            *inner.unary
+        }
+        _ => node,
+    };
+    Ok((
+        text,
+        Node {
+            kind: NodeKind::MemAccess(MemAccess {
+                unary: Box::new(node),
+                member: Box::new(mem),
+            }),
+            ty: Some(Box::new(mem_ty)),
+        },
+    ))
+}
+
+// An lvalue of type va_list
+fn parse_va_list(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
+    let (i, ap) = parse_assign(text)?;
+    let ty = ap.ty.clone().unwrap();
+    if ty.kind != TypeKind::Pointer || ty.ptr_to.unwrap().kind != TypeKind::Char {
+        return context("Not a va_list", fail)(text);
+    }
+    Ok((i, ap))
+}
+
+// member_designator = ident ("." ident | "[" num "]")*
+// Byte offset of a member from the start of a struct type
+fn parse_member_offset(text: &str, ty: Type) -> IResult<&str, u16, VerboseError<&str>> {
+    let (mut t, ident) = take_while1(is_ident)(text)?;
+    let mut ty = ty;
+    let mut offset = 0;
+    let mut field = Some(ident);
+    loop {
+        if let Some(ident) = field {
+            if ty.kind != TypeKind::Struct {
+                return context("Not a struct", fail)(t);
+            }
+            match ty.find_member(ident) {
+                Some(member) => {
+                    offset += member.offset;
+                    ty = *member.ty.clone();
+                }
+                None => return context("Not such member", fail)(t),
+            }
+        }
+        let (i, _) = multispace0(t)?;
+        let (i, s) = opt(alt((tag("."), tag("["))))(i)?;
+        match s {
+            Some(".") => {
+                let (i, _) = multispace0(i)?;
+                let (i, ident) = take_while1(is_ident)(i)?;
+                field = Some(ident);
+                t = i;
+            }
+            Some(_) => {
+                if ty.kind != TypeKind::Array {
+                    return context("Not an array", fail)(i);
+                }
+                let (j, idx) = delimited(multispace0, digit1, multispace0)(i)?;
+                let (j, _) = tag("]")(j)?;
+                ty = *ty.ptr_to.unwrap();
+                let elem_offset = idx
+                    .parse::<u16>()
+                    .ok()
+                    .and_then(|idx| ty.size.checked_mul(idx))
+                    .and_then(|elem_offset| offset.checked_add(elem_offset));
+                match elem_offset {
+                    Some(elem_offset) => offset = elem_offset,
+                    None => return context("Array index out of range", fail)(i),
+                }
+                field = None;
+                t = j;
+            }
+            None => return Ok((t, offset)),
+        }
+    }
+}
+
+// sizeof_operand = "(" type_name ")" | unary
+fn parse_sizeof_operand(text: &str) -> IResult<&str, Type, VerboseError<&str>> {
+    let (i, s) = opt(tag("("))(text)?;
+    if s.is_some() {
+        let (i, _) = multispace0(i)?;
+        if is_typename(i) {
+            let (i, ty) = parse_typename(i)?;
+            let (i, _) = multispace0(i)?;
+            let (i, _) = tag(")")(i)?;
+            return Ok((i, ty));
+        }
+    }
+    let (i, node) = parse_unary(text)?;
+    match node.ty {
+        Some(ty) => Ok((i, *ty)),
+        None => context("Unable to calculate", fail)(i),
+    }
+}
+
+// primary = num | str | char | ident ( "(" args ")" )? | "(" expr ")"
 fn parse_primary(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
     let (_, num) = opt(digit1)(text)?;
     if num.is_some() {
@@ -1621,6 +3809,10 @@ fn parse_primary(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
     if str.is_some() {
         return parse_str(text);
     }
+    let (_, char) = opt(tag("'"))(text)?;
+    if char.is_some() {
+        return parse_char(text);
+    }
     let (_, ident) = opt(take_while1(is_ident))(text)?;
     if ident.is_some() {
         return parse_ident(text);
@@ -1636,60 +3828,63 @@ fn parse_primary(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
     context("Unexpected symbol", fail)(text)
 }
 
+// num = ("0x" hex | "0b" bin | "0" oct | dec) ("u" | "U" | "l" | "L")*
+// Digits may be separated by "_". Literals which do not fit in int are unsigned int.
 fn parse_num(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
-    let (i, s) = opt(alt((tag("0x"), tag("0b"))))(text)?;
-    if let Some(s) = s {
-        match s {
-            "0x" => {
-                let (i, val) = hex_digit1(i)?;
-                let val: u16 = u16::from_str_radix(val, 16).unwrap();
-                Ok((
-                    i,
-                    Node {
-                        kind: NodeKind::Num(Num { val }),
-                        ty: Some(Box::new(create_int_type())),
-                    },
-                ))
-            }
-            "0b" => {
-                let (i, val) = many0(alt((tag("0"), tag("1"))))(i)?;
-                let val = val.concat();
-                let val: u16 = u16::from_str_radix(&val, 2).unwrap();
-                Ok((
-                    i,
-                    Node {
-                        kind: NodeKind::Num(Num { val }),
-                        ty: Some(Box::new(create_int_type())),
-                    },
-                ))
-            }
-            _ => {
-                unreachable!()
-            }
+    let (i, prefix) = opt(alt((tag("0x"), tag("0X"), tag("0b"), tag("0B"))))(text)?;
+    let (radix, i) = match prefix {
+        Some("0x" | "0X") => (16, i),
+        Some(_) => (2, i),
+        None if text.len() > 1
+            && text.starts_with('0')
+            && text[1..].starts_with(|c: char| c.is_ascii_digit() || c == '_') =>
+        {
+            (8, &text[1..])
         }
-    } else {
-        let (i, val) = digit1(text)?;
-        let val: u16 = val.parse().unwrap();
-        Ok((
-            i,
-            Node {
-                kind: NodeKind::Num(Num { val }),
-                ty: Some(Box::new(create_int_type())),
-            },
-        ))
+        None => (10, text),
+    };
+    let (i, token) = take_while(is_ident)(i)?;
+    let digits = token.trim_end_matches(['u', 'U', 'l', 'L']);
+    let suffix = &token[digits.len()..];
+
+    if digits.is_empty() {
+        return context("Missing digits in integer literal", fail)(text);
     }
+    if digits.starts_with('_') || digits.ends_with('_') || digits.contains("__") {
+        return context("Invalid digit separator", fail)(text);
+    }
+    let mut val: u32 = 0;
+    for c in digits.chars().filter(|&c| c != '_') {
+        match c.to_digit(radix) {
+            Some(d) => val = val * radix + d,
+            None => return context("Invalid digit in integer literal", fail)(text),
+        }
+        if val > 0xffff {
+            return context("Integer literal is too large", fail)(text);
+        }
+    }
+    let unsigned = suffix.matches(['u', 'U']).count();
+    let long = suffix.matches(['l', 'L']).count();
+    if unsigned > 1 || long > 2 {
+        return context("Invalid integer literal suffix", fail)(text);
+    }
+
+    let ty = if unsigned == 1 || val > 0x7fff {
+        create_uint_type()
+    } else {
+        create_int_type()
+    };
+    Ok((
+        i,
+        Node {
+            kind: NodeKind::Num(Num { val: val as i32 }),
+            ty: Some(Box::new(ty)),
+        },
+    ))
 }
 
 fn parse_str(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
-    let (i, _) = tag("\"")(text)?;
-    let (i, str) = take_until("\"")(i)?;
-    let (i, _) = tag("\"")(i)?;
-
-    if !str.is_ascii() {
-        return context("Not ascii", fail)(i);
-    }
-    let mut str = convert_escape_string(str);
-    str.push('\0');
+    let (i, str) = parse_str_literal(text)?;
 
     unsafe {
         let label = format!("str{}", STR_LABEL_COUNT);
@@ -1699,19 +3894,13 @@ fn parse_str(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
         let gstr = Scope {
             kind: ScopeKind::Str,
             name: label.clone(),
-            ty: Some(Box::new(Type {
-                kind: TypeKind::Array,
-                ptr_to: Some(Box::new(Type {
-                    kind: TypeKind::Char,
-                    ptr_to: None,
-                    size: 1,
-                    members: None,
-                })),
-                size: str_len,
-                members: None,
-            })),
+            ty: Some(Box::new(create_array_type(create_char_type(), str_len))),
             str: Some(str.to_string()),
             offset: None,
+            init: None,
+            storage: Storage::Default,
+            attrs: Attrs::default(),
+            is_defined: false,
         };
 
         GLOBALS.push(gstr.clone());
@@ -1730,90 +3919,380 @@ fn parse_str(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
     }
 }
 
-fn convert_escape_string(s: &str) -> String {
+// String literal with escapes converted and terminated by '\0'.
+// Adjacent literals are concatenated. Each char of the result holds one byte.
+fn parse_str_literal(text: &str) -> IResult<&str, String, VerboseError<&str>> {
+    let (mut i, _) = tag("\"")(text)?;
     let mut str = String::new();
-    let mut is_escape = false;
-
-    for c in s.chars() {
-        if is_escape {
-            match c {
-                'a' => str.push(7 as char),
-                'b' => str.push(8 as char),
-                't' => str.push('\t'),
-                'n' => str.push('\n'),
-                'v' => str.push(11 as char),
-                'f' => str.push(12 as char),
-                'r' => str.push('\r'),
-                '0' => str.push('\0'),
-                _ => {
-                    str.push(c);
-                }
+    loop {
+        let (j, seq) = parse_char_seq(i, '"')?;
+        str.push_str(&seq);
+        match opt(preceded(multispace0, tag("\"")))(j)? {
+            (j, Some(_)) => i = j,
+            (j, None) => {
+                i = j;
+                break;
+            }
+        }
+    }
+    str.push('\0');
+    Ok((i, str))
+}
+
+// char = "'" (c-char | escape) "'"
+fn parse_char(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
+    let (i, _) = tag("'")(text)?;
+    let (i, str) = parse_char_seq(i, '\'')?;
+    let mut chars = str.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok((i, new_num(c as i32))),
+        (None, _) => context("Empty character constant", fail)(text),
+        _ => context("Multi-character constant", fail)(text),
+    }
+}
+
+// Characters up to the closing quote with escapes converted
+fn parse_char_seq(text: &str, quote: char) -> IResult<&str, String, VerboseError<&str>> {
+    let mut str = String::new();
+    let mut i = text;
+    loop {
+        let mut chars = i.chars();
+        match chars.next() {
+            Some(c) if c == quote => return Ok((chars.as_str(), str)),
+            Some('\\') => {
+                let (j, c) = parse_escape(i)?;
+                str.push(c as char);
+                i = j;
+            }
+            Some('\n') | None => {
+                return context("Missing terminating quote", fail)(text);
+            }
+            Some(c) if !c.is_ascii() => {
+                return context("Not ascii", fail)(i);
+            }
+            Some(c) => {
+                str.push(c);
+                i = chars.as_str();
             }
-            is_escape = false;
-        } else if c == '\\' {
-            is_escape = true;
-        } else {
-            str.push(c);
         }
     }
-    str
+}
+
+// escape = "\" (simple-escape | octal-digit{1,3} | "x" hex-digit+)
+fn parse_escape(text: &str) -> IResult<&str, u8, VerboseError<&str>> {
+    let (i, _) = tag("\\")(text)?;
+    let mut chars = i.chars();
+    let c = match chars.next() {
+        Some(c) => c,
+        None => return context("Invalid escape sequence", fail)(text),
+    };
+    let val = match c {
+        'a' => 7,
+        'b' => 8,
+        't' => 9,
+        'n' => 10,
+        'v' => 11,
+        'f' => 12,
+        'r' => 13,
+        'e' => 27,
+        '\\' | '\'' | '"' | '?' => c as u32,
+        '0'..='7' => {
+            let digits: String = i.chars().take_while(|c| c.is_digit(8)).take(3).collect();
+            let val = u32::from_str_radix(&digits, 8).unwrap();
+            if val > 0xff {
+                return context("Octal escape sequence out of range", fail)(text);
+            }
+            return Ok((&i[digits.len()..], val as u8));
+        }
+        'x' => {
+            let i = chars.as_str();
+            let digits: String = i.chars().take_while(|c| c.is_ascii_hexdigit()).collect();
+            if digits.is_empty() {
+                return context("\\x used with no following hex digits", fail)(text);
+            }
+            match u8::from_str_radix(&digits, 16) {
+                Ok(val) => return Ok((&i[digits.len()..], val)),
+                Err(_) => return context("Hex escape sequence out of range", fail)(text),
+            }
+        }
+        _ => return context("Unknown escape sequence", fail)(text),
+    };
+    Ok((chars.as_str(), val as u8))
 }
 
 fn parse_ident(text: &str) -> IResult<&str, Node, VerboseError<&str>> {
     let (i, ident) = take_while1(is_ident)(text)?;
     let (i, _) = multispace0(i)?;
+    let func = unsafe {
+        GLOBALS
+            .iter()
+            .find(|g| g.kind == ScopeKind::Func && g.name == ident)
+            .cloned()
+    };
+    // Static functions are called by their unique label
+    let name = match func.as_ref().map(|f| &f.storage) {
+        Some(Storage::Static(label)) => label.clone(),
+        _ => ident.to_string(),
+    };
+    let is_var = |scope: &Scope| scope.kind == ScopeKind::Var && scope.name == ident;
+    if ident == "NULL" && unsafe { !LOCALS.iter().any(is_var) && !GLOBALS.iter().any(is_var) } {
+        return Ok((i, new_num(0)));
+    }
+    // A variable of function pointer type is called by the postfix "(" of parse_unary
     let (i, s) = opt(tag("("))(i)?;
-    if s.is_some() {
-        let (i, args) = separated_list0(
-            permutation((multispace0, tag(","), multispace0)),
-            parse_assign,
-        )(i)?;
-        let (i, _) = multispace0(i)?;
-        let (i, _) = tag(")")(i)?;
-        return Ok((
-            i,
-            Node {
-                kind: NodeKind::FuncCall(FuncCall {
-                    name: ident.to_string(),
-                    args,
-                }),
-                ty: Some(Box::new(create_int_type())),
-            },
-        ));
+    if s.is_some() && unsafe { !LOCALS.iter().any(is_var) && !GLOBALS.iter().any(is_var) } {
+        // Functions not declared yet are assumed to return int
+        if func.is_none() {
+            warn(
+                text,
+                WarningKind::ImplicitDeclaration,
+                format!("implicit declaration of function '{}'", ident),
+            );
+        }
+        let (i, args) = parse_args(i)?;
+        let func_ty = func.and_then(|f| f.ty).map(|ty| *ty);
+        return new_call(i, name, None, func_ty.as_ref(), args);
+    }
+    let i = if s.is_some() { &text[ident.len()..] } else { i };
+    if let Some(val) = find_enum_const(ident) {
+        return Ok((i, new_num(val)));
     }
     unsafe {
         if let Some(lvar) = LOCALS
             .iter()
+            .rev()
             .find(|&scope| scope.kind == ScopeKind::Var && scope.name == ident)
         {
+            let (name, is_global) = match &lvar.storage {
+                Storage::Static(label) => (label.clone(), true),
+                Storage::Extern => (lvar.name.clone(), true),
+                Storage::Default => (lvar.name.clone(), false),
+            };
             Ok((
                 i,
                 Node {
                     kind: NodeKind::Var(Var {
-                        name: lvar.name.clone(),
-                        offset: lvar.offset.unwrap(),
-                        is_global: false,
+                        name,
+                        offset: lvar.offset.unwrap_or(0),
+                        is_global,
                     }),
                     ty: Some(lvar.ty.clone().unwrap()),
                 },
             ))
         } else if let Some(gvar) = GLOBALS
             .iter()
+            .rev()
             .find(|&scope| scope.kind == ScopeKind::Var && scope.name == ident)
         {
+            let name = match &gvar.storage {
+                Storage::Static(label) => label.clone(),
+                _ => gvar.name.clone(),
+            };
             Ok((
                 i,
                 Node {
                     kind: NodeKind::Var(Var {
-                        name: gvar.name.clone(),
+                        name,
                         offset: 0,
                         is_global: true,
                     }),
                     ty: Some(gvar.ty.clone().unwrap()),
                 },
             ))
+        } else if let Some(func) = func {
+            // Function designator
+            Ok((
+                i,
+                Node {
+                    kind: NodeKind::Var(Var {
+                        name,
+                        offset: 0,
+                        is_global: true,
+                    }),
+                    ty: func.ty,
+                },
+            ))
         } else {
             context("Undefined variable", fail)(i)
         }
     }
 }
+
+// args = (assign ("," assign)*)? ")"
+fn parse_args(text: &str) -> IResult<&str, Vec<Node>, VerboseError<&str>> {
+    let (i, _) = multispace0(text)?;
+    let (i, args) = separated_list0(
+        permutation((multispace0, tag(","), multispace0)),
+        parse_assign,
+    )(i)?;
+    let (i, _) = multispace0(i)?;
+    let (i, _) = tag(")")(i)?;
+    Ok((i, args))
+}
+
+// Call of a function of type func_ty, by name or through the callee address.
+// Without a type the function is assumed to return int.
+fn new_call<'a>(
+    text: &'a str,
+    name: String,
+    callee: Option<Node>,
+    func_ty: Option<&Type>,
+    args: Vec<Node>,
+) -> IResult<&'a str, Node, VerboseError<&'a str>> {
+    let mut args: Vec<Node> = args.into_iter().map(decay).collect();
+    if args
+        .iter()
+        .any(|arg| arg.ty.as_ref().is_some_and(|ty| ty.is_incomplete()))
+    {
+        return context("Argument has incomplete type", fail)(text);
+    }
+    let mut varargs = Vec::new();
+    if let Some(params) = func_ty.and_then(|ty| ty.params.as_ref()) {
+        if args.len() > params.len() && !func_ty.unwrap().is_variadic {
+            return context("Too many arguments", fail)(text);
+        }
+        if args.len() < params.len() {
+            return context("Too few arguments", fail)(text);
+        }
+        for (param, arg) in params.iter().zip(&args) {
+            check_qualifiers(text, param, arg);
+            check_pointer_types(text, param, arg);
+        }
+        varargs = args.split_off(params.len());
+        if varargs
+            .iter()
+            .any(|arg| arg.ty.clone().unwrap().kind == TypeKind::Struct)
+        {
+            return context("Invalid variadic argument", fail)(text);
+        }
+    }
+    let ret_ty = func_ty
+        .and_then(|ty| ty.ptr_to.clone())
+        .map_or(create_int_type(), |ty| *ty);
+    // A returned struct is stored in a temporary of the caller
+    if ret_ty.kind == TypeKind::Struct {
+        check_stack_align(text, &ret_ty)?;
+        let ptr_ty = create_pointer_type(ret_ty.clone());
+        let tmp = local_var(alloc_local(&ret_ty), ret_ty.clone());
+        args.insert(
+            0,
+            Node {
+                kind: NodeKind::Addr(Unary {
+                    unary: Box::new(tmp),
+                }),
+                ty: Some(Box::new(ptr_ty)),
+            },
+        );
+    }
+    if args.len() > MAX_REG_ARGS {
+        return context("Too many arguments", fail)(text);
+    }
+    Ok((
+        text,
+        Node {
+            kind: NodeKind::FuncCall(FuncCall {
+                name,
+                callee: callee.map(Box::new),
+                args,
+                varargs,
+            }),
+            ty: Some(Box::new(ret_ty)),
+        },
+    ))
+}
+
+// Evaluate a constant expression
+fn eval(node: &Node) -> Option<i32> {
+    match eval_addr(node)? {
+        (None, val) => Some(val),
+        _ => None,
+    }
+}
+
+// Evaluate a constant expression which may be the address of a global (label, offset)
+fn eval_addr(node: &Node) -> Option<(Option<String>, i32)> {
+    let binary = |n: &Binary, f: fn(i32, i32) -> Option<i32>| {
+        let val = f(eval(&n.left)?, eval(&n.right)?)?;
+        Some((None, val & 0xffff))
+    };
+    let is_uint = |node: &Node| node.ty.as_ref().is_some_and(|ty| ty.is_uint());
+    match &node.kind {
+        NodeKind::Num(n) => Some((None, n.val & 0xffff)),
+        NodeKind::Div(n) if is_uint(node) => binary(n, |l, r| (r != 0).then(|| l / r)),
+        NodeKind::Mod(n) if is_uint(node) => binary(n, |l, r| (r != 0).then(|| l % r)),
+        NodeKind::Lt(n) if is_uint(&n.left) || is_uint(&n.right) => {
+            binary(n, |l, r| Some((l < r) as i32))
+        }
+        NodeKind::Le(n) if is_uint(&n.left) || is_uint(&n.right) => {
+            binary(n, |l, r| Some((l <= r) as i32))
+        }
+        NodeKind::Add(n) => binary(n, |l, r| Some(l.wrapping_add(r))),
+        NodeKind::Sub(n) => binary(n, |l, r| Some(l.wrapping_sub(r))),
+        NodeKind::Mul(n) => binary(n, |l, r| Some(l.wrapping_mul(r))),
+        NodeKind::Div(n) => binary(n, |l, r| {
+            (r != 0).then(|| (l as i16 as i32).wrapping_div(r as i16 as i32))
+        }),
+        NodeKind::Mod(n) => binary(n, |l, r| {
+            (r != 0).then(|| (l as i16 as i32).wrapping_rem(r as i16 as i32))
+        }),
+        NodeKind::BitAnd(n) => binary(n, |l, r| Some(l & r)),
+        NodeKind::BitOr(n) => binary(n, |l, r| Some(l | r)),
+        NodeKind::BitXor(n) => binary(n, |l, r| Some(l ^ r)),
+        NodeKind::Eq(n) => binary(n, |l, r| Some((l == r) as i32)),
+        NodeKind::Ne(n) => binary(n, |l, r| Some((l != r) as i32)),
+        NodeKind::Lt(n) => binary(n, |l, r| Some(((l as i16) < (r as i16)) as i32)),
+        NodeKind::Le(n) => binary(n, |l, r| Some(((l as i16) <= (r as i16)) as i32)),
+        NodeKind::LogAnd(n) => Some((None, (eval(&n.left)? != 0 && eval(&n.right)? != 0) as i32)),
+        NodeKind::LogOr(n) => Some((None, (eval(&n.left)? != 0 || eval(&n.right)? != 0) as i32)),
+        NodeKind::Sll(n) => binary(n, |l, r| Some(l.wrapping_shl(r as u32 & 0xf))),
+        NodeKind::Srl(n) => binary(n, |l, r| Some(l >> (r & 0xf))),
+        NodeKind::Sra(n) => binary(n, |l, r| Some((l as i16 >> (r & 0xf)) as i32)),
+        NodeKind::Not(n) => Some((None, (eval(&n.unary)? == 0) as i32)),
+        NodeKind::Neg(n) => Some((None, -eval(&n.unary)? & 0xffff)),
+        NodeKind::BitNot(n) => Some((None, !eval(&n.unary)? & 0xffff)),
+        NodeKind::Cast(n) => {
+            let (label, val) = eval_addr(&n.unary)?;
+            if node.ty.clone().unwrap().kind == TypeKind::Char {
+                label.is_none().then_some((None, val & 0xff))
+            } else {
+                Some((label, val))
+            }
+        }
+        // Arrays, strings and functions decay to the address of their label
+        NodeKind::Var(n)
+            if n.is_global
+                && matches!(
+                    node.ty.clone().unwrap().kind,
+                    TypeKind::Array | TypeKind::Func
+                ) =>
+        {
+            Some((Some(n.name.clone()), 0))
+        }
+        NodeKind::Addr(n) => eval_lval(&n.unary),
+        NodeKind::PtrAdd(n) | NodeKind::PtrSub(n) => {
+            let (label, base) = eval_addr(&n.left)?;
+            let size = node.ty.clone().unwrap().ptr_to.unwrap().size as i32;
+            let index = eval(&n.right)? as i16 as i32;
+            let offset = match node.kind {
+                NodeKind::PtrAdd(_) => base.wrapping_add(index.wrapping_mul(size)),
+                _ => base.wrapping_sub(index.wrapping_mul(size)),
+            };
+            Some((label, offset & 0xffff))
+        }
+        _ => None,
+    }
+}
+
+// Evaluate the address of a global lvalue
+fn eval_lval(node: &Node) -> Option<(Option<String>, i32)> {
+    match &node.kind {
+        NodeKind::Var(n) if n.is_global => Some((Some(n.name.clone()), 0)),
+        NodeKind::Deref(n) => eval_addr(&n.unary),
+        NodeKind::MemAccess(n) if !n.member.is_bitfield() => {
+            let (label, offset) = eval_lval(&n.unary)?;
+            Some((label, offset + n.member.offset as i32))
+        }
+        NodeKind::FuncCall(n) => Some((Some(n.name.clone()), 0)),
+        _ => None,
+    }
+}
@@ -1,9 +1,17 @@
 #[derive(Debug, PartialEq, Clone)]
 pub struct Type {
     pub kind: TypeKind,
-    pub ptr_to: Option<Box<Type>>,
+    pub ptr_to: Option<Box<Type>>, // Pointee, element or return type
     pub size: u16,
+    pub align: u16,
     pub members: Option<Vec<Member>>,
+    pub is_unsigned: bool,
+    pub is_const: bool,
+    pub is_volatile: bool,         // Every access is generated as written
+    pub params: Option<Vec<Type>>, // Parameter types of a function, None for "func"
+    pub is_variadic: bool,         // Function taking more arguments after params
+    pub tag: Option<String>,       // Struct tag, which identifies an incomplete struct
+    pub is_union: bool,            // Struct whose members all start at offset 0
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -22,7 +30,32 @@ pub fn create_int_type() -> Type {
         kind: TypeKind::Int,
         ptr_to: None,
         size: 2,
+        align: 2,
         members: None,
+        is_unsigned: false,
+        is_const: false,
+        is_volatile: false,
+        params: None,
+        is_variadic: false,
+        tag: None,
+        is_union: false,
+    }
+}
+
+pub fn create_uint_type() -> Type {
+    Type {
+        kind: TypeKind::Int,
+        ptr_to: None,
+        size: 2,
+        align: 2,
+        members: None,
+        is_unsigned: true,
+        is_const: false,
+        is_volatile: false,
+        params: None,
+        is_variadic: false,
+        tag: None,
+        is_union: false,
     }
 }
 
@@ -31,7 +64,15 @@ pub fn create_char_type() -> Type {
         kind: TypeKind::Char,
         ptr_to: None,
         size: 1,
+        align: 1,
         members: None,
+        is_unsigned: false,
+        is_const: false,
+        is_volatile: false,
+        params: None,
+        is_variadic: false,
+        tag: None,
+        is_union: false,
     }
 }
 
@@ -40,14 +81,244 @@ pub fn create_func_type() -> Type {
         kind: TypeKind::Func,
         ptr_to: None,
         size: 2,
+        align: 2,
+        members: None,
+        is_unsigned: false,
+        is_const: false,
+        is_volatile: false,
+        params: None,
+        is_variadic: false,
+        tag: None,
+        is_union: false,
+    }
+}
+
+// Function returning ret
+pub fn create_function_type(ret: Type, params: Vec<Type>, is_variadic: bool) -> Type {
+    Type {
+        ptr_to: Some(Box::new(ret)),
+        params: Some(params),
+        is_variadic,
+        ..create_func_type()
+    }
+}
+
+// struct or union tag, declared but not defined yet
+pub fn create_incomplete_struct_type(tag: &str, is_union: bool) -> Type {
+    Type {
+        kind: TypeKind::Struct,
+        size: 0,
+        align: 1,
+        tag: Some(tag.to_string()),
+        is_union,
+        ..create_int_type()
+    }
+}
+
+pub fn create_pointer_type(ty: Type) -> Type {
+    Type {
+        kind: TypeKind::Pointer,
+        ptr_to: Some(Box::new(ty)),
+        size: 2,
+        align: 2,
         members: None,
+        is_unsigned: false,
+        is_const: false,
+        is_volatile: false,
+        params: None,
+        is_variadic: false,
+        tag: None,
+        is_union: false,
     }
 }
 
+pub fn create_array_type(ty: Type, len: u16) -> Type {
+    Type {
+        kind: TypeKind::Array,
+        size: ty.size * len,
+        align: ty.align,
+        ptr_to: Some(Box::new(ty)),
+        members: None,
+        is_unsigned: false,
+        is_const: false,
+        is_volatile: false,
+        params: None,
+        is_variadic: false,
+        tag: None,
+        is_union: false,
+    }
+}
+
+impl Type {
+    // This type with the qualifiers of other added, e.g. for a member of a const struct
+    pub fn qualified_by(&self, other: &Type) -> Type {
+        let mut ty = self.clone();
+        ty.is_const |= other.is_const;
+        ty.is_volatile |= other.is_volatile;
+        ty
+    }
+
+    pub fn unqualified(&self) -> Type {
+        Type {
+            is_const: false,
+            is_volatile: false,
+            ..self.clone()
+        }
+    }
+
+    // Whether pointers to this type and to other may be mixed. The old "func"
+    // type stands for any function.
+    pub fn is_compatible_pointee(&self, other: &Type) -> bool {
+        let is_any_func = |ty: &Type| ty.kind == TypeKind::Func && ty.params.is_none();
+        is_any_func(self)
+            || is_any_func(other)
+            || self.unqualified().is_compatible(&other.unqualified())
+    }
+
+    // Whether data of this type can be placed in read-only memory
+    pub fn is_const_data(&self) -> bool {
+        match self.kind {
+            TypeKind::Array => self.ptr_to.as_ref().unwrap().is_const_data(),
+            _ => self.is_const,
+        }
+    }
+
+    // Whether two declarations of a symbol agree. An array may omit its size,
+    // a struct may be incomplete in one of them and the qualifiers of function
+    // parameters don't matter.
+    pub fn is_compatible(&self, other: &Type) -> bool {
+        if self == other {
+            return true;
+        }
+        let same_qualifiers =
+            self.is_const == other.is_const && self.is_volatile == other.is_volatile;
+        let pointee = |ty: &Type| ty.ptr_to.as_ref().unwrap().clone();
+        match (self.kind, other.kind) {
+            (TypeKind::Array, TypeKind::Array) => {
+                pointee(self).is_compatible(&pointee(other))
+                    && (self.size == 0 || other.size == 0 || self.size == other.size)
+            }
+            (TypeKind::Pointer, TypeKind::Pointer) => {
+                same_qualifiers && pointee(self).is_compatible(&pointee(other))
+            }
+            // A tag declared again in an inner block is another struct, which
+            // is told apart by its members unless one of them is incomplete
+            (TypeKind::Struct, TypeKind::Struct) => {
+                same_qualifiers
+                    && self.is_union == other.is_union
+                    && self.tag.is_some()
+                    && self.tag == other.tag
+                    && (self.is_incomplete() || other.is_incomplete() || self.is_same_struct(other))
+            }
+            (TypeKind::Func, TypeKind::Func) if self.is_function() && other.is_function() => {
+                let params = self.params.as_ref().unwrap();
+                let other_params = other.params.as_ref().unwrap();
+                pointee(self).is_compatible(&pointee(other))
+                    && self.is_variadic == other.is_variadic
+                    && params.len() == other_params.len()
+                    && params
+                        .iter()
+                        .zip(other_params)
+                        .all(|(ty, other)| ty.unqualified().is_compatible(&other.unqualified()))
+            }
+            _ => false,
+        }
+    }
+
+    // A struct declared by its tag whose members are not known yet
+    pub fn is_incomplete(&self) -> bool {
+        self.kind == TypeKind::Struct && self.members.is_none()
+    }
+
+    // Whether a struct of this type can be copied to other, ignoring qualifiers
+    pub fn is_same_struct(&self, other: &Type) -> bool {
+        self.kind == TypeKind::Struct
+            && other.kind == TypeKind::Struct
+            && self.size == other.size
+            && self.unaligned().members == other.unaligned().members
+    }
+
+    // The member named ident, which may be inside an anonymous struct or union
+    // member. Its offset is from the start of this struct.
+    pub fn find_member(&self, ident: &str) -> Option<Member> {
+        for member in self.members.iter().flatten() {
+            if member.name == ident {
+                return Some(member.clone());
+            }
+            if member.name.is_empty() && member.ty.kind == TypeKind::Struct {
+                if let Some(mut inner) = member.ty.find_member(ident) {
+                    inner.offset += member.offset;
+                    return Some(inner);
+                }
+            }
+        }
+        None
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.kind == TypeKind::Int || self.kind == TypeKind::Char
+    }
+
+    // unsigned int, which is not promoted to int
+    pub fn is_uint(&self) -> bool {
+        self.kind == TypeKind::Int && self.is_unsigned
+    }
+
+    // A function with a parameter list, whose value is its address
+    pub fn is_function(&self) -> bool {
+        self.kind == TypeKind::Func && self.params.is_some()
+    }
+
+    pub fn is_pointer(&self) -> bool {
+        self.kind == TypeKind::Pointer || self.kind == TypeKind::Array
+    }
+
+    // The same type with 1 byte alignment, accessed byte by byte when it is
+    // wider. The elements of an array and the members of a struct are too.
+    pub fn unaligned(&self) -> Type {
+        let mut ty = self.clone();
+        ty.align = 1;
+        if let Some(ptr_to) = &ty.ptr_to {
+            if ty.kind == TypeKind::Array {
+                ty.ptr_to = Some(Box::new(ptr_to.unaligned()));
+            }
+        }
+        for member in ty.members.iter_mut().flatten() {
+            *member.ty = member.ty.unaligned();
+        }
+        ty
+    }
+}
+
+// Round offset up to a multiple of align
+pub fn align_to(offset: u16, align: u16) -> u16 {
+    offset.div_ceil(align) * align
+}
+
 // Struct member
+//
+// Bit-fields are laid out as follows:
+// - A bit-field lives in a storage unit of its declared type, so a unit is
+//   one byte for char and two bytes for int, and is aligned like that type.
+// - Consecutive bit-fields fill the same unit from the LSB up, the first one
+//   at bit 0, while they fit and the declared types have the same size.
+// - A bit-field never straddles two units. One that doesn't fit in the rest
+//   of the current unit starts a new unit at the next aligned offset.
+// - A zero width bit-field declares nothing and closes the current unit, so
+//   the next bit-field starts a new one.
+// - An unnamed bit-field only takes up its bits as padding.
+// - In a union every bit-field starts at bit 0 of a unit at offset 0.
 #[derive(Debug, PartialEq, Clone)]
 pub struct Member {
     pub ty: Box<Type>,
     pub name: String,
-    pub offset: u16,
+    pub offset: u16,     // Byte offset, of the storage unit for a bit-field
+    pub bit_offset: u16, // Bit position in the storage unit, from the LSB
+    pub bit_width: u16,  // 0 unless a bit-field
+}
+
+impl Member {
+    pub fn is_bitfield(&self) -> bool {
+        self.bit_width != 0
+    }
 }
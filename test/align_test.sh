@@ -0,0 +1,11 @@
+#!/bin/bash
+
+. ./check.sh
+
+cargo run -- zktc-c/assert.zktc.c zktc-c/align_test.zktc.c -o asm/align_test.asm
+zktc-asm asm/align_test.asm -o mem/align_test.mem -b 0xb000
+
+
+echo "=== align test ==="
+
+check mem/align_test.mem
\ No newline at end of file
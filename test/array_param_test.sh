@@ -0,0 +1,11 @@
+#!/bin/bash
+
+. ./check.sh
+
+cargo run -- zktc-c/assert.zktc.c zktc-c/array_param_test.zktc.c -o asm/array_param_test.asm
+zktc-asm asm/array_param_test.asm -o mem/array_param_test.mem -b 0xb000
+
+
+echo "=== array param test ==="
+
+check mem/array_param_test.mem
\ No newline at end of file
@@ -0,0 +1,11 @@
+#!/bin/bash
+
+. ./check.sh
+
+cargo run -- zktc-c/assert.zktc.c zktc-c/asm_test.zktc.c -o asm/asm_test.asm
+zktc-asm asm/asm_test.asm -o mem/asm_test.mem -b 0xb000
+
+
+echo "=== asm test ==="
+
+check mem/asm_test.mem
\ No newline at end of file
@@ -0,0 +1,16 @@
+#!/bin/bash
+
+. ./check.sh
+
+cargo run -- zktc-c/assert.zktc.c zktc-c/attribute_test.zktc.c -o asm/attribute_test.asm
+zktc-asm asm/attribute_test.asm -o mem/attribute_test.mem -b 0xb000
+
+if cargo run -- zktc-c/section_name_error.zktc.c -o asm/section_name_error.asm 2>/dev/null; then
+	echo "\e[31msection_name_error compiled"
+	exit 1
+fi
+
+
+echo "=== attribute test ==="
+
+check mem/attribute_test.mem
\ No newline at end of file
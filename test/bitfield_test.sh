@@ -0,0 +1,11 @@
+#!/bin/bash
+
+. ./check.sh
+
+cargo run -- zktc-c/assert.zktc.c zktc-c/bitfield_test.zktc.c -o asm/bitfield_test.asm
+zktc-asm asm/bitfield_test.asm -o mem/bitfield_test.mem -b 0xb000
+
+
+echo "=== bitfield test ==="
+
+check mem/bitfield_test.mem
\ No newline at end of file
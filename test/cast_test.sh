@@ -0,0 +1,11 @@
+#!/bin/bash
+
+. ./check.sh
+
+cargo run -- zktc-c/assert.zktc.c zktc-c/cast_test.zktc.c -o asm/cast_test.asm
+zktc-asm asm/cast_test.asm -o mem/cast_test.mem -b 0xb000
+
+
+echo "=== cast test ==="
+
+check mem/cast_test.mem
\ No newline at end of file
@@ -0,0 +1,11 @@
+#!/bin/bash
+
+. ./check.sh
+
+cargo run -- zktc-c/assert.zktc.c zktc-c/char_test.zktc.c -o asm/char_test.asm
+zktc-asm asm/char_test.asm -o mem/char_test.mem -b 0xb000
+
+
+echo "=== char test ==="
+
+check mem/char_test.mem
\ No newline at end of file
@@ -0,0 +1,11 @@
+#!/bin/bash
+
+. ./check.sh
+
+cargo run -- zktc-c/assert.zktc.c zktc-c/const_expr_test.zktc.c -o asm/const_expr_test.asm
+zktc-asm asm/const_expr_test.asm -o mem/const_expr_test.mem -b 0xb000
+
+
+echo "=== const_expr test ==="
+
+check mem/const_expr_test.mem
\ No newline at end of file
@@ -0,0 +1,11 @@
+#!/bin/bash
+
+. ./check.sh
+
+cargo run -- zktc-c/assert.zktc.c zktc-c/const_test.zktc.c -o asm/const_test.asm
+zktc-asm asm/const_test.asm -o mem/const_test.mem -b 0xb000
+
+
+echo "=== const test ==="
+
+check mem/const_test.mem
\ No newline at end of file
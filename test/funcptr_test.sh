@@ -0,0 +1,11 @@
+#!/bin/bash
+
+. ./check.sh
+
+cargo run -- zktc-c/assert.zktc.c zktc-c/funcptr_test.zktc.c -o asm/funcptr_test.asm
+zktc-asm asm/funcptr_test.asm -o mem/funcptr_test.mem -b 0xb000
+
+
+echo "=== funcptr test ==="
+
+check mem/funcptr_test.mem
\ No newline at end of file
@@ -0,0 +1,11 @@
+#!/bin/bash
+
+. ./check.sh
+
+cargo run -- zktc-c/assert.zktc.c zktc-c/global_init_test.zktc.c -o asm/global_init_test.asm
+zktc-asm asm/global_init_test.asm -o mem/global_init_test.mem -b 0xb000
+
+
+echo "=== global init test ==="
+
+check mem/global_init_test.mem
\ No newline at end of file
@@ -0,0 +1,16 @@
+#!/bin/bash
+
+. ./check.sh
+
+cargo run -- zktc-c/assert.zktc.c zktc-c/incomplete_test.zktc.c -o asm/incomplete_test.asm
+zktc-asm asm/incomplete_test.asm -o mem/incomplete_test.mem -b 0xb000
+
+if cargo run -- zktc-c/struct_redef_error.zktc.c -o asm/struct_redef_error.asm 2>/dev/null; then
+	echo "\e[31mstruct_redef_error compiled"
+	exit 1
+fi
+
+
+echo "=== incomplete test ==="
+
+check mem/incomplete_test.mem
\ No newline at end of file
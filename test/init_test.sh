@@ -0,0 +1,11 @@
+#!/bin/bash
+
+. ./check.sh
+
+cargo run -- zktc-c/assert.zktc.c zktc-c/init_test.zktc.c -o asm/init_test.asm
+zktc-asm asm/init_test.asm -o mem/init_test.mem -b 0xb000
+
+
+echo "=== init test ==="
+
+check mem/init_test.mem
\ No newline at end of file
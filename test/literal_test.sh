@@ -8,4 +8,12 @@ zktc-asm asm/literal_test.asm -o mem/literal_test.mem -b 0xb000
 
 echo "=== literal test ==="
 
-check mem/literal_test.mem
\ No newline at end of file
+check mem/literal_test.mem
+
+cargo run -- zktc-c/assert.zktc.c zktc-c/literal_test2.zktc.c -o asm/literal_test2.asm
+zktc-asm asm/literal_test2.asm -o mem/literal_test2.mem -b 0xb000
+
+
+echo "=== literal test2 ==="
+
+check mem/literal_test2.mem
\ No newline at end of file
@@ -0,0 +1,11 @@
+#!/bin/bash
+
+. ./check.sh
+
+cargo run -- zktc-c/assert.zktc.c zktc-c/neg_test.zktc.c -o asm/neg_test.asm
+zktc-asm asm/neg_test.asm -o mem/neg_test.mem -b 0xb000
+
+
+echo "=== neg test ==="
+
+check mem/neg_test.mem
\ No newline at end of file
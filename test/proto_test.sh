@@ -0,0 +1,11 @@
+#!/bin/bash
+
+. ./check.sh
+
+cargo run -- zktc-c/assert.zktc.c zktc-c/proto_test1.zktc.c zktc-c/proto_test2.zktc.c -o asm/proto_test.asm
+zktc-asm asm/proto_test.asm -o mem/proto_test.mem -b 0xb000
+
+
+echo "=== proto test ==="
+
+check mem/proto_test.mem
@@ -0,0 +1,11 @@
+#!/bin/bash
+
+. ./check.sh
+
+cargo run -- zktc-c/assert.zktc.c zktc-c/ptrdiff_test.zktc.c -o asm/ptrdiff_test.asm
+zktc-asm asm/ptrdiff_test.asm -o mem/ptrdiff_test.mem -b 0xb000
+
+
+echo "=== ptrdiff test ==="
+
+check mem/ptrdiff_test.mem
\ No newline at end of file
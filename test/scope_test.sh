@@ -0,0 +1,11 @@
+#!/bin/bash
+
+. ./check.sh
+
+cargo run -- zktc-c/assert.zktc.c zktc-c/scope_test.zktc.c -o asm/scope_test.asm
+zktc-asm asm/scope_test.asm -o mem/scope_test.mem -b 0xb000
+
+
+echo "=== scope test ==="
+
+check mem/scope_test.mem
\ No newline at end of file
@@ -0,0 +1,11 @@
+#!/bin/bash
+
+. ./check.sh
+
+cargo run -- zktc-c/assert.zktc.c zktc-c/sizeof_test.zktc.c -o asm/sizeof_test.asm
+zktc-asm asm/sizeof_test.asm -o mem/sizeof_test.mem -b 0xb000
+
+
+echo "=== sizeof test ==="
+
+check mem/sizeof_test.mem
\ No newline at end of file
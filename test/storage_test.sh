@@ -0,0 +1,11 @@
+#!/bin/bash
+
+. ./check.sh
+
+cargo run -- zktc-c/assert.zktc.c zktc-c/storage_test1.zktc.c zktc-c/storage_test2.zktc.c -o asm/storage_test.asm
+zktc-asm asm/storage_test.asm -o mem/storage_test.mem -b 0xb000
+
+
+echo "=== storage test ==="
+
+check mem/storage_test.mem
\ No newline at end of file
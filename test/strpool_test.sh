@@ -0,0 +1,11 @@
+#!/bin/bash
+
+. ./check.sh
+
+cargo run -- zktc-c/assert.zktc.c zktc-c/strpool_test.zktc.c -o asm/strpool_test.asm
+zktc-asm asm/strpool_test.asm -o mem/strpool_test.mem -b 0xb000
+
+
+echo "=== string pool test ==="
+
+check mem/strpool_test.mem
\ No newline at end of file
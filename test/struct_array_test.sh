@@ -0,0 +1,11 @@
+#!/bin/bash
+
+. ./check.sh
+
+cargo run -- zktc-c/assert.zktc.c zktc-c/struct_array_test.zktc.c -o asm/struct_array_test.asm
+zktc-asm asm/struct_array_test.asm -o mem/struct_array_test.mem -b 0xb000
+
+
+echo "=== struct_array test ==="
+
+check mem/struct_array_test.mem
\ No newline at end of file
@@ -0,0 +1,17 @@
+#!/bin/bash
+
+. ./check.sh
+
+cargo run -- zktc-c/assert.zktc.c zktc-c/struct_copy_test.zktc.c -o asm/struct_copy_test.asm
+zktc-asm asm/struct_copy_test.asm -o mem/struct_copy_test.mem -b 0xb000
+
+# A struct returning function has only two registers left for its parameters
+if cargo run -- zktc-c/struct_ret_args_error.zktc.c -o asm/struct_ret_args_error.asm 2>/dev/null; then
+	echo "\e[31mstruct_ret_args_error compiled"
+	exit 1
+fi
+
+
+echo "=== struct copy test ==="
+
+check mem/struct_copy_test.mem
\ No newline at end of file
@@ -0,0 +1,11 @@
+#!/bin/bash
+
+. ./check.sh
+
+cargo run -- zktc-c/assert.zktc.c zktc-c/union_test.zktc.c -o asm/union_test.asm
+zktc-asm asm/union_test.asm -o mem/union_test.mem -b 0xb000
+
+
+echo "=== union test ==="
+
+check mem/union_test.mem
\ No newline at end of file
@@ -0,0 +1,11 @@
+#!/bin/bash
+
+. ./check.sh
+
+cargo run -- zktc-c/assert.zktc.c zktc-c/variadic_test.zktc.c -o asm/variadic_test.asm
+zktc-asm asm/variadic_test.asm -o mem/variadic_test.mem -b 0xb000
+
+
+echo "=== variadic test ==="
+
+check mem/variadic_test.mem
\ No newline at end of file
@@ -0,0 +1,111 @@
+struct padded {
+	char c;
+	int i;
+	char d;
+};
+
+struct __attribute__((packed)) wire {
+	char kind;
+	int len;
+	char tail[3];
+	int crc;
+};
+
+struct header {
+	char tag;
+	int words[2];
+} __attribute__((packed));
+
+struct outer {
+	char a;
+	struct padded p;
+};
+
+struct inner {
+	int x;
+	char y;
+};
+
+struct __attribute__((packed)) holder {
+	char c;
+	struct inner s;
+	struct inner t[2];
+};
+
+struct holder gh;
+
+struct wire gw = {1, 0x1234, {2, 3, 4}, 0x5678};
+int offsetof_tbl[2] = {4, 5};
+char gc = 1;
+int gx = 0x1234;
+
+int main()
+{
+	char c;
+	int x;
+	struct padded p;
+	struct wire w;
+	struct header h;
+	int *px;
+
+	assert(6, sizeof(struct padded), 1);
+	assert(2, _Alignof(struct padded), 2);
+	assert(0, offsetof(struct padded, c), 3);
+	assert(2, offsetof(struct padded, i), 4);
+	assert(4, offsetof(struct padded, d), 5);
+
+	assert(8, sizeof(struct wire), 6);
+	assert(1, _Alignof(struct wire), 7);
+	assert(1, offsetof(struct wire, len), 8);
+	assert(3, offsetof(struct wire, tail), 9);
+	assert(4, offsetof(struct wire, tail[1]), 10);
+	assert(6, offsetof(struct wire, crc), 11);
+	assert(5, sizeof(struct header), 12);
+
+	assert(8, sizeof(struct outer), 13);
+	assert(4, offsetof(struct outer, p.i), 14);
+
+	px = &x;
+	assert(0, (int)px & 1, 15);
+	px = &p.i;
+	assert(0, (int)px & 1, 16);
+
+	w.kind = 7;
+	w.len = 0x4321;
+	w.tail[2] = 9;
+	w.crc = -2;
+	assert(7, w.kind, 17);
+	assert(0x4321, w.len, 18);
+	assert(9, w.tail[2], 19);
+	assert(-2, w.crc, 20);
+
+	h.words[1] = 300;
+	assert(300, h.words[1], 21);
+
+	assert(0x1234, gw.len, 22);
+	assert(4, gw.tail[2], 23);
+	assert(0x5678, gw.crc, 24);
+
+	p.c = 1;
+	p.i = 2;
+	p.d = 3;
+	assert(6, p.c + p.i + p.d, 25);
+
+	assert(5, offsetof_tbl[1], 26);
+
+	// Globals are aligned like locals
+	assert(0, (int)&gx & 1, 27);
+	gx = gx + gc;
+	assert(0x1235, gx, 28);
+
+	// Members of a struct inside a packed struct are unaligned too
+	gh.s.x = 0x2345;
+	gh.t[1].x = 0x3456;
+	assert(1, offsetof(struct holder, s), 29);
+	assert(0x2345, gh.s.x, 30);
+	assert(0x3456, gh.t[1].x, 31);
+	gh.s = gh.t[1];
+	assert(0x3456, gh.s.x, 32);
+
+	return 0;
+}
@@ -0,0 +1,78 @@
+int grid[3][4];
+
+int sum(int a[10], int n)
+{
+	int i;
+	int total = 0;
+	for (i = 0; i < n; i = i + 1)
+		total = total + a[i];
+	return total;
+}
+
+int size_of_param(int a[10])
+{
+	return sizeof(a);
+}
+
+int fill(int a[], int n)
+{
+	int local[2];
+	a[n - 1] = 7;
+	a = local;
+	a[0] = 3;
+	return local[0];
+}
+
+int cell(int m[][4], int r, int c)
+{
+	return m[r][c];
+}
+
+int row_stride(int m[][4])
+{
+	return (int)(m + 1) - (int)m;
+}
+
+int row_bytes(int m[][4])
+{
+	return sizeof(*m);
+}
+
+int count(char s[])
+{
+	int n = 0;
+	while (s[n])
+		n = n + 1;
+	return n;
+}
+
+int main()
+{
+	int a[10];
+	int i;
+	int j;
+	char str[6] = "hello";
+	int (*f)(int *, int) = sum;
+
+	for (i = 0; i < 10; i = i + 1)
+		a[i] = i;
+	for (i = 0; i < 3; i = i + 1)
+		for (j = 0; j < 4; j = j + 1)
+			grid[i][j] = i * 10 + j;
+
+	assert(45, sum(a, 10), 1);
+	assert(6, sum(a + 1, 3), 2);
+	assert(2, size_of_param(a), 3);
+	assert(3, fill(a, 5), 4);
+	assert(7, a[4], 5);
+	assert(23, cell(grid, 2, 3), 6);
+	assert(10, cell(grid + 1, 0, 0), 7);
+	assert(21, cell(grid, 0, 9), 8);
+	assert(8, row_bytes(grid), 9);
+	assert(8, row_stride(grid), 13);
+	assert(5, count(str), 10);
+	assert(3, count("abc"), 11);
+	assert(13, f(a, 5), 12);
+
+	return 0;
+}
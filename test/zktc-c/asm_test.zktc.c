@@ -0,0 +1,75 @@
+struct regs {
+	int lo;
+	char c;
+	unsigned flag : 1;
+	unsigned bits : 3;
+};
+
+int g;
+int arr[4];
+
+int add(int a, int b)
+{
+	__asm__("add %0, %1" : "+r"(a) : "r"(b));
+	return a;
+}
+
+int main()
+{
+	int x = 0;
+	int y = 41;
+	int v;
+	int *p = &y;
+	char ch;
+	struct regs r;
+
+	__asm__("mov %0, %1" : "=r"(x) : "r"(y));
+	assert(41, x, 1);
+
+	assert(7, add(3, 4), 2);
+
+	x = 10;
+	__asm__ __volatile__("sub %[res], %[b]" : [res] "+r"(x) : [b] "r"(y));
+	assert(-31, x, 3);
+
+	__asm__("addi %0, %1, %2" : "=r"(x) : "r"(y), "i"(5));
+	assert(46, x, 4);
+
+	__asm__("addi %0, %0, 1" : "=r"(x) : "0"(y));
+	assert(42, x, 5);
+
+	// Operands don't get the clobbered registers
+	__asm__("addi a0, zero, 1\n\taddi a1, zero, 2\n\tmov %0, %1"
+		: "=r"(x)
+		: "r"(y)
+		: "a0", "a1");
+	assert(41, x, 6);
+
+	__asm__ volatile("lw %0, %1, 0" : "=r"(v) : "r"(p));
+	assert(41, v, 7);
+
+	__asm__("addi %0, zero, 9" : "=r"(g));
+	assert(9, g, 8);
+
+	__asm__("addi %0, zero, 12" : "=r"(arr[2]));
+	assert(12, arr[2], 9);
+
+	__asm__("addi %0, zero, 13" : "=r"(*p));
+	assert(13, y, 10);
+
+	__asm__("addi %0, zero, 30\n\taddi %1, zero, 7" : "=r"(r.lo), "=r"(ch));
+	assert(30, r.lo, 11);
+	assert(7, ch, 12);
+
+	r.bits = 0;
+	__asm__("addi %0, zero, 1\n\taddi %1, zero, 5" : "=r"(r.flag), "=r"(r.bits));
+	assert(1, r.flag, 13);
+	assert(5, r.bits, 14);
+
+	__asm__("mov %0, %1" : "=r"(x) : "r"(y == 13));
+	assert(1, x, 15);
+
+	__asm__("" : : : "memory");
+
+	return 0;
+}
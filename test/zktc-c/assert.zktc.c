@@ -8,7 +8,6 @@ int assert(int expected, int actual, int num)
 	else
 	{
 		// If fails, returns num and trap
-		__asm__("mov a0, a2");
-		__asm__("trap");
+		__asm__ volatile("mov a0, %0\n\ttrap" : : "r"(num) : "a0");
 	}
-}
\ No newline at end of file
+}
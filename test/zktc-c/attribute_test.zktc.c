@@ -0,0 +1,135 @@
+struct __attribute__((aligned(4))) wide {
+	char c;
+};
+
+struct pair {
+	char a;
+	int b;
+} __attribute__((packed, aligned));
+
+struct spaced {
+	char c;
+	int a __attribute__((aligned(4)));
+};
+
+struct spaced gs;
+
+int counter __attribute__((section("__vars"))) = 5;
+__attribute__((section("__vars"), used)) int limit = 9;
+static int hidden __attribute__((section("__hidden")));
+int plain = 3;
+char pad = 1;
+int buf[2] __attribute__((aligned(4)));
+
+// Defined by the file-scope asm below
+int triple(int x);
+
+__asm__("triple:\n"
+	"\tmov a1, a0\n"
+	"\tadd a0, a1\n"
+	"\tadd a0, a1\n"
+	"\tjalr zero, ra, 0");
+
+__asm__("");
+
+// Data in the code, whose size is counted to keep the globals aligned
+__asm__("table:\n"
+	"\t.byte 1, 2 ; two bytes\n"
+	"; nothing\n");
+
+// An odd number of bytes, after which the next function is realigned
+__asm__("odd:\n"
+	"\t.byte 3\n");
+
+__attribute__((naked)) int twice(int x)
+{
+	__asm__("add a0, a0\n\tjalr zero, ra, 0");
+}
+
+__naked__ int inc(int x)
+{
+	__asm__("addi a0, a0, 1\n\tjalr zero, ra, 0");
+}
+
+int add(int a, int b) __attribute__((section("__text2")));
+
+__attribute__((section("__text2"))) int add(int a, int b)
+{
+	return a + b;
+}
+
+__attribute__((aligned(8))) int four()
+{
+	return 4;
+}
+
+int five() __attribute__((aligned(4)))
+{
+	return 5;
+}
+
+__attribute__((noreturn)) int stop();
+__attribute__((interrupt, used)) int handler()
+{
+	hidden = hidden + 1;
+	return hidden;
+}
+
+int main()
+{
+	int x = 1;
+	__attribute__((aligned(2))) char c = 7;
+	char d __attribute__((unused)) = 8;
+	static int calls __attribute__((section("__vars")));
+	static char sc __attribute__((aligned(8)));
+	char lc __attribute__((aligned(2)));
+	static struct wide w;
+	struct pair p = {1, 2};
+
+	assert(4, sizeof(struct wide), 1);
+	assert(4, _Alignof(struct wide), 2);
+	assert(4, sizeof(struct pair), 3);
+	assert(2, _Alignof(struct pair), 4);
+	assert(2, p.b, 5);
+	w.c = 6;
+	assert(6, w.c, 6);
+
+	assert(15, triple(5), 7);
+	assert(14, twice(7), 8);
+	assert(8, inc(7), 9);
+	assert(1, x, 10);
+
+	assert(5, counter, 11);
+	assert(9, limit, 12);
+	counter = counter + limit;
+	assert(14, counter, 13);
+	assert(3, plain, 14);
+	assert(7, add(3, 4), 15);
+	calls = calls + 1;
+	assert(1, calls, 16);
+
+	assert(1, handler(), 17);
+	assert(2, handler(), 18);
+	assert(7, c, 19);
+	assert(8, d, 20);
+
+	// aligned places variables and functions at a multiple of it
+	assert(0, (int)buf & 3, 21);
+	assert(0, (int)&sc & 7, 22);
+	assert(0, (int)&lc & 1, 23);
+	assert(0, (int)four & 7, 24);
+	assert(4, four(), 25);
+	assert(1, pad, 26);
+	assert(0, (int)&plain & 1, 27);
+
+	// An aligned member raises the alignment of its struct
+	assert(4, offsetof(struct spaced, a), 28);
+	assert(8, sizeof(struct spaced), 29);
+	assert(0, (int)&gs.a & 3, 30);
+
+	assert(0, (int)five & 3, 31);
+	assert(5, five(), 32);
+	assert(0, (int)twice & 1, 33);
+
+	return 0;
+}
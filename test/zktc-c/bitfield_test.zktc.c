@@ -0,0 +1,126 @@
+struct flags {
+	unsigned ready : 1;
+	unsigned mode : 3;
+	unsigned count : 12;
+};
+
+struct mixed {
+	char tag;
+	unsigned lo : 4;
+	unsigned hi : 4;
+	int word;
+	char c1 : 3;
+	char c2 : 5;
+};
+
+struct gaps {
+	unsigned a : 3;
+	unsigned : 5;
+	unsigned b : 3;
+	unsigned : 0;
+	unsigned c : 2;
+};
+
+struct signed_bits {
+	int s : 4;
+	unsigned u : 4;
+};
+
+struct labelled {
+	char *name;
+	int x;
+};
+
+struct flags gf = {1, 5, 1000};
+struct mixed gm = {.hi = 9, .lo = 6, .c2 = 17, .tag = 3, .c1 = 2, .word = 0x1234};
+struct labelled gl = {"a", 5};
+
+int main()
+{
+	struct flags f;
+	struct mixed m;
+	struct gaps g;
+	struct signed_bits sb;
+	struct flags lf = {0, 7, 4095};
+	struct mixed lm = {.lo = 3, .hi = 12};
+	unsigned *raw;
+
+	assert(2, sizeof(struct flags), 1);
+	assert(8, sizeof(struct mixed), 2);
+	assert(4, sizeof(struct gaps), 3);
+	assert(2, offsetof(struct mixed, lo), 4);
+	assert(6, offsetof(struct mixed, c2), 5);
+
+	f.ready = 0;
+	f.mode = 0;
+	f.count = 0;
+	f.mode = 6;
+	assert(0, f.ready, 6);
+	assert(6, f.mode, 7);
+	assert(0, f.count, 8);
+	f.count = 4095;
+	f.ready = 1;
+	assert(1, f.ready, 9);
+	assert(6, f.mode, 10);
+	assert(4095, f.count, 11);
+	raw = (unsigned *)&f;
+	assert(65533, *raw, 12);
+
+	f.mode = 9;
+	assert(1, f.mode, 13);
+	f.mode = f.mode + 2;
+	assert(3, f.mode, 14);
+	assert(1, f.ready, 15);
+
+	m.tag = 1;
+	m.word = 2;
+	m.lo = 15;
+	m.hi = 0;
+	m.c1 = 7;
+	m.c2 = 31;
+	assert(15, m.lo, 16);
+	assert(0, m.hi, 17);
+	m.hi = 10;
+	assert(15, m.lo, 18);
+	assert(10, m.hi, 19);
+	assert(7, m.c1, 20);
+	assert(31, m.c2, 21);
+	assert(1, m.tag, 22);
+	assert(2, m.word, 23);
+
+	g.a = 5;
+	g.b = 6;
+	g.c = 3;
+	assert(5, g.a, 24);
+	assert(6, g.b, 25);
+	assert(3, g.c, 26);
+
+	sb.s = -3;
+	sb.u = 13;
+	assert(-3, sb.s, 27);
+	assert(13, sb.u, 28);
+	sb.s = 7;
+	assert(7, sb.s, 29);
+
+	assert(1, gf.ready, 30);
+	assert(5, gf.mode, 31);
+	assert(1000, gf.count, 32);
+	assert(3, gm.tag, 33);
+	assert(6, gm.lo, 34);
+	assert(9, gm.hi, 35);
+	assert(4660, gm.word, 36);
+	assert(2, gm.c1, 37);
+	assert(17, gm.c2, 38);
+	assert(97, gl.name[0], 39);
+	assert(5, gl.x, 40);
+
+	assert(0, lf.ready, 41);
+	assert(7, lf.mode, 42);
+	assert(4095, lf.count, 43);
+	assert(0, lm.tag, 44);
+	assert(3, lm.lo, 45);
+	assert(12, lm.hi, 46);
+	assert(0, lm.c2, 47);
+
+	return 0;
+}
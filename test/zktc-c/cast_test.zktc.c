@@ -0,0 +1,80 @@
+struct packet
+{
+	char kind;
+	char len;
+};
+
+typedef int myint;
+typedef struct packet *packet_ptr;
+
+int test1()
+{
+	return (char)0x1234;
+}
+
+int test2()
+{
+	return (int)(char)300;
+}
+
+int test3()
+{
+	char c = 200;
+	return (int)c;
+}
+
+int test4()
+{
+	int x = 5;
+	int addr = &x;
+	int *p = (int *)addr;
+	return *p;
+}
+
+int test5()
+{
+	char buf[2];
+	buf[0] = 1;
+	buf[1] = 2;
+	struct packet *pk = (struct packet *)buf;
+	return pk->len;
+}
+
+int test6()
+{
+	return (myint)(char)257;
+}
+
+int test7()
+{
+	char buf[2];
+	buf[0] = 3;
+	buf[1] = 4;
+	return ((packet_ptr)buf)->kind;
+}
+
+int test8()
+{
+	return sizeof((char)1);
+}
+
+int test9()
+{
+	int x = 0x0102;
+	return *(char *)&x + (char)x * 2;
+}
+
+int main()
+{
+	assert(0x34, test1(), 1);
+	assert(44, test2(), 2);
+	assert(200, test3(), 3);
+	assert(5, test4(), 4);
+	assert(2, test5(), 5);
+	assert(1, test6(), 6);
+	assert(3, test7(), 7);
+	assert(1, test8(), 8);
+	assert(6, test9(), 9);
+
+	return 0;
+}
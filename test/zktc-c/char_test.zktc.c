@@ -0,0 +1,22 @@
+char quote[] = "say \"hi\"";
+
+int main()
+{
+	assert(97, 'a', 1);
+	assert(10, '\n', 2);
+	assert(39, '\'', 3);
+	assert(92, '\\', 4);
+	assert(65, '\x41', 5);
+	assert(65, '\101', 6);
+	assert(0, '\0', 7);
+	assert(255, "\xff"[0], 8);
+	assert(34, "\""[0], 9);
+	assert(43, sizeof(quote) - quote[8] + '\"', 10);
+	assert(8, "a\1012"[2] - '*', 11);
+	assert(3, sizeof("\x41\\"), 12);
+	assert(1, 'b' - 'a', 13);
+	assert(63, "\?"[0], 14);
+	assert(7, "\a"[0], 15);
+
+	return 0;
+}
@@ -0,0 +1,73 @@
+#define LEN 3
+
+enum { A, B, C = 10, D };
+enum color { RED = 1, GREEN = RED << 2, BLUE };
+enum { NEG = -5, NEXT };
+enum { PROD = -2 * -3, SHIFTED = -1 << 15 };
+
+struct bits {
+	unsigned lo : LEN + 1;
+	unsigned hi : sizeof(int) * 8 - (LEN + 1);
+};
+
+_Static_assert(sizeof(struct bits) == 2, "bits must fit in a word");
+_Static_assert(D == 11);
+_Static_assert(-1 * -1 == 1, "negative factors");
+
+int a[LEN * 2];
+char buf[sizeof(int) * 4 + 1];
+int table[D];
+char wrap[(char)300];
+int one[-1 * -1];
+int g = C * 2 + sizeof(a);
+enum color gc = BLUE;
+
+int pick(enum color c)
+{
+	return c == GREEN;
+}
+
+int main()
+{
+	enum color c = GREEN;
+	struct bits b;
+	int m[B + 1][LEN];
+	enum { LOCAL = 7, A_SHADOW = A + 100 };
+
+	_Static_assert(LOCAL * 2 == 14, "local constants");
+
+	assert(12, sizeof(a), 1);
+	assert(9, sizeof(buf), 2);
+	assert(22, sizeof(table), 3);
+	assert(44, sizeof(wrap), 4);
+	assert(32, g, 5);
+	assert(0, A, 6);
+	assert(1, B, 7);
+	assert(10, C, 8);
+	assert(11, D, 9);
+	assert(4, GREEN, 10);
+	assert(5, BLUE, 11);
+	assert(5, gc, 12);
+	assert(-4, NEXT, 13);
+	assert(1, pick(c), 14);
+	assert(2, sizeof(enum color), 15);
+	assert(12, sizeof(m), 16);
+	assert(100, A_SHADOW, 17);
+
+	b.lo = 15;
+	b.hi = 4095;
+	assert(15, b.lo, 18);
+	assert(4095, b.hi, 19);
+
+	{
+		int LOCAL = 3;
+		assert(3, LOCAL, 20);
+	}
+	assert(7, LOCAL, 21);
+	assert(6, PROD, 22);
+	assert(-32768, SHIFTED, 23);
+	assert(2, sizeof(one), 24);
+	assert(1, a[-1 * -1 - 1] + 1, 25);
+
+	return 0;
+}
@@ -0,0 +1,51 @@
+const int table[4] = {1, 1, 2, 3};
+const char name[] = "zktc";
+const int answer = 42;
+int counter = 0;
+
+struct pair {
+	int a;
+	int b;
+};
+
+const struct pair origin = {3, 4};
+
+int sum(const int *p, int n)
+{
+	int s = 0;
+	for (int i = 0; i < n; i = i + 1)
+		s = s + p[i];
+	return s;
+}
+
+int main()
+{
+	const int x = 5;
+	int const y = 6;
+	const int *p = &x;
+	int *const q = &counter;
+	volatile int v = 0;
+	volatile int *vp = &v;
+	const volatile int cv = 9;
+
+	assert(7, sum(table, 4), 1);
+	assert(5, *p, 2);
+	assert(6, y, 3);
+	p = &y;
+	assert(6, *p, 4);
+	*q = 8;
+	assert(8, counter, 5);
+	*vp = 3;
+	*vp = *vp + 1;
+	assert(4, v, 6);
+	assert(42, answer, 7);
+	assert(116, name[2], 8);
+	assert(5, sizeof(name), 9);
+	assert(7, origin.a + origin.b, 10);
+	assert(9, cv, 11);
+	assert(2, sizeof(const int), 12);
+	assert(2, sizeof(char *const), 13);
+	assert(3, ((const int *)table)[3], 14);
+
+	return 0;
+}
@@ -0,0 +1,94 @@
+typedef int (*binop)(int, int);
+
+struct ops {
+	int (*apply)(int, int);
+	char *name;
+};
+
+int add(int a, int b)
+{
+	return a + b;
+}
+
+int sub(int a, int b)
+{
+	return a - b;
+}
+
+int mul(int a, int b)
+{
+	return a * b;
+}
+
+static int twice(int x)
+{
+	return x * 2;
+}
+
+int apply(int (*f)(int, int), int a, int b)
+{
+	return f(a, b);
+}
+
+int call_param(int g(int), int x)
+{
+	return g(x);
+}
+
+binop pick(int k)
+{
+	if (k)
+		return sub;
+	return add;
+}
+
+int (*gtable[3])(int, int) = {add, sub, &mul};
+struct ops gops = {mul, "mul"};
+
+int main()
+{
+	int (*fp)(int, int);
+	int (*one)(int) = twice;
+	binop table[2];
+	struct ops o;
+	struct ops *po;
+	int i;
+	int sum;
+
+	fp = add;
+	assert(7, fp(3, 4), 1);
+	assert(7, (*fp)(3, 4), 2);
+	fp = &sub;
+	assert(-1, fp(3, 4), 3);
+	assert(10, one(5), 4);
+	assert(12, (*one)(6), 5);
+
+	table[0] = add;
+	table[1] = mul;
+	assert(9, table[0](4, 5), 6);
+	assert(20, table[1](4, 5), 7);
+
+	sum = 0;
+	for (i = 0; i < 3; i = i + 1)
+		sum = sum + gtable[i](6, 2);
+	assert(24, sum, 8);
+
+	assert(15, apply(mul, 3, 5), 9);
+	assert(8, apply(add, apply(sub, 9, 4), 3), 10);
+	assert(14, call_param(twice, 7), 11);
+	assert(2, pick(1)(5, 3), 12);
+	assert(8, pick(0)(5, 3), 13);
+
+	o.apply = sub;
+	po = &o;
+	assert(6, o.apply(10, 4), 14);
+	assert(6, po->apply(10, 4), 15);
+	assert(21, gops.apply(3, 7), 16);
+	assert(109, gops.name[0], 17);
+
+	assert(2, sizeof(fp), 18);
+	assert(6, sizeof(gtable), 19);
+	assert(2, sizeof(int (*)(int)), 20);
+
+	return 0;
+}
@@ -0,0 +1,50 @@
+struct point
+{
+	int x;
+	int y;
+};
+
+struct line
+{
+	char color;
+	struct point from;
+	struct point to;
+};
+
+int scalar = 1234;
+char small = 0x1ff;
+int negative = 0 - 3;
+int expr = (1 + 2) * 4 << 1;
+int table[5] = {1, 2, 3};
+int matrix[2][3] = {{1, 2, 3}, {4, 5, 6}};
+char text[8] = "hello";
+char flags[4] = {1, 2};
+struct point origin = {7, 8};
+struct line lines[2] = {{1, {2, 3}, {4, 5}}, {6, {7, 8}, {9, 10}}};
+int *ptr = &scalar;
+int *elem = &table[2];
+int *elem2 = table + 1;
+int *field = &lines[1].to.x;
+char *msg = "world";
+char **msg_ptr = &msg;
+
+int main()
+{
+	assert(1234, scalar, 1);
+	assert(0xff, small, 2);
+	assert(0 - 3, negative, 3);
+	assert(24, expr, 4);
+	assert(3, table[2] + table[3] + table[4], 5);
+	assert(6, matrix[1][2], 6);
+	assert(111, text[4], 7);
+	assert(0, text[7], 8);
+	assert(2, flags[1] + flags[2], 9);
+	assert(8, origin.y, 10);
+	assert(9, lines[1].to.x, 11);
+	assert(3, *elem, 12);
+	assert(2, *elem2, 13);
+	assert(9, *field, 14);
+	assert(1, *ptr == scalar && msg[0] == 119 && (*msg_ptr)[4] == 100, 15);
+
+	return 0;
+}
@@ -0,0 +1,124 @@
+struct dev;
+typedef struct node Node;
+
+struct dev *dev_open(int id);
+int dev_id(struct dev *d);
+
+struct node {
+	int val;
+	struct node *next;
+};
+
+struct pkt {
+	int len;
+	char data[];
+};
+
+struct a {
+	struct b *peer;
+	int x;
+};
+
+struct b {
+	struct a *peer;
+	int y;
+};
+
+struct dev {
+	int id;
+	int flags;
+};
+
+struct dev devs[3];
+
+struct dev *dev_open(int id)
+{
+	devs[id].id = id * 11;
+	return &devs[id];
+}
+
+int dev_id(struct dev *d)
+{
+	return d->id;
+}
+
+int sum(Node *n)
+{
+	int s = 0;
+	while (n) {
+		s = s + n->val;
+		n = n->next;
+	}
+	return s;
+}
+
+struct span {
+	int lo;
+	int hi;
+} make_span(int lo, int hi)
+{
+	struct span s;
+	s.lo = lo;
+	s.hi = hi;
+	return s;
+}
+
+int main()
+{
+	struct dev *d;
+	Node n1;
+	Node n2;
+	struct node n3;
+	char buf[8];
+	struct pkt *p;
+	struct a x;
+	struct b y;
+	int i;
+
+	d = dev_open(2);
+	assert(22, dev_id(d), 1);
+	assert(4, sizeof(struct dev), 2);
+
+	n1.val = 1;
+	n1.next = &n2;
+	n2.val = 20;
+	n2.next = &n3;
+	n3.val = 300;
+	n3.next = 0;
+	assert(321, sum(&n1), 3);
+	assert(300, n1.next->next->val, 4);
+	assert(4, sizeof(Node), 5);
+
+	assert(2, sizeof(struct pkt), 6);
+	p = (struct pkt *)buf;
+	p->len = 3;
+	for (i = 0; i < p->len; i = i + 1)
+		p->data[i] = 'a' + i;
+	assert('a', buf[2], 7);
+	assert('c', buf[4], 8);
+	assert(2, p->data - (char *)p, 9);
+	assert('b', *(p->data + 1), 10);
+
+	x.peer = &y;
+	y.peer = &x;
+	x.x = 5;
+	y.y = 7;
+	assert(7, x.peer->y, 11);
+	assert(5, x.peer->peer->x, 12);
+
+	{
+		struct local;
+		struct local *lp;
+		struct local {
+			char c;
+			struct local *self;
+		} l;
+		l.c = 9;
+		l.self = &l;
+		lp = &l;
+		assert(9, lp->self->self->c, 13);
+	}
+	assert(4, make_span(2, 6).hi - make_span(2, 6).lo, 14);
+
+	return 0;
+}
@@ -0,0 +1,106 @@
+struct point
+{
+	int x;
+	int y;
+};
+
+struct rect
+{
+	char color;
+	struct point from;
+	struct point to;
+};
+
+int global[] = {1, 2, [5] = 6};
+char name[] = "zktc";
+struct rect grect = {.to.y = 4, .color = 1};
+
+int test1()
+{
+	int a[4] = {1, 2, 3, 4};
+	return a[0] + a[3];
+}
+
+int test2()
+{
+	int a[10] = {1, 2};
+	return a[1] + a[2] + a[9];
+}
+
+int test3()
+{
+	int t[] = {1, 2, 3};
+	return sizeof(t) + t[2];
+}
+
+int test4()
+{
+	struct point p = {3, 4};
+	return p.x * p.y;
+}
+
+int test5()
+{
+	struct point p = {.y = 5};
+	return p.x + p.y;
+}
+
+int test6()
+{
+	int a[8] = {[3] = 7, 8, [0] = 1};
+	return a[0] + a[3] + a[4] + a[5];
+}
+
+int test7()
+{
+	struct rect r = {2, {3, 4}, .to = {5, 6}};
+	return r.color + r.from.y + r.to.x;
+}
+
+int test8()
+{
+	struct rect rs[2] = {[1].to.y = 9, [0] = {1}};
+	return rs[0].color + rs[1].to.y + rs[1].from.x;
+}
+
+int test9()
+{
+	char s[] = "abc";
+	char t[8] = "ab";
+	return sizeof(s) + s[1] - t[1] + t[5];
+}
+
+int test10()
+{
+	int m[2][3] = {{1, 2, 3}, {4, 5, 6}};
+	int z[2][2] = {{1}, {[1] = 2}};
+	return m[1][2] + z[1][1] + z[0][1];
+}
+
+int test11()
+{
+	int x = 4;
+	int a[3] = {x, x * 2, x + x + x};
+	return a[2];
+}
+
+int main()
+{
+	assert(5, test1(), 1);
+	assert(2, test2(), 2);
+	assert(9, test3(), 3);
+	assert(12, test4(), 4);
+	assert(5, test5(), 5);
+	assert(16, test6(), 6);
+	assert(11, test7(), 7);
+	assert(10, test8(), 8);
+	assert(4, test9(), 9);
+	assert(8, test10(), 10);
+	assert(12, test11(), 11);
+	assert(12, sizeof(global), 12);
+	assert(6, global[5] + global[2] + global[4], 13);
+	assert(5, sizeof(name) + name[4], 14);
+	assert(5, grect.color + grect.to.y + grect.from.x, 15);
+
+	return 0;
+}
@@ -0,0 +1,24 @@
+int main()
+{
+	unsigned int u = 40000;
+	unsigned v = 3;
+	long l = 7L;
+
+	assert(8, 010, 1);
+	assert(0, 00, 2);
+	assert(1000, 1_000, 3);
+	assert(0xffff, 0xFF_FF, 4);
+	assert(5, 0b0101u, 5);
+	assert(2, sizeof(1UL), 6);
+	assert(1, 0xffff > 1, 7);
+	assert(0, 0 - 1 > 1, 8);
+	assert(1, u > 30000, 9);
+	assert(13333, u / v, 10);
+	assert(1, u % v, 11);
+	assert(1, 65535u / 40000, 12);
+	assert(25535, 65535u % 40000, 13);
+	assert(1, (0 - 2) <= 65534u && 7 == l, 14);
+	assert(2, sizeof(unsigned char) + sizeof(short) - 1, 15);
+
+	return 0;
+}
@@ -0,0 +1,51 @@
+struct point {
+	int x;
+	int y;
+};
+
+int g = -5;
+int tbl[3] = {-1, -300, 7};
+
+int neg(int x)
+{
+	return -x;
+}
+
+int main()
+{
+	int a[3];
+	int *p;
+	struct point pt;
+	struct point *pp;
+
+	a[0] = 1;
+	a[1] = 2;
+	a[2] = 3;
+	p = a;
+	pt.x = 4;
+	pt.y = 40;
+	pp = &pt;
+
+	assert(-3, -a[2], 1);
+	assert(-1, -*p, 2);
+	assert(-2, -p[1], 3);
+	assert(-40, -pt.y, 4);
+	assert(-4, -pp->x, 5);
+	assert(3, - -3, 6);
+	assert(3, -(-3), 7);
+	assert(-5, g, 8);
+	assert(-300, tbl[1], 9);
+	assert(-1, tbl[0], 10);
+	assert(7, neg(-7), 11);
+	assert(1, -1 < 0, 12);
+	assert(0, -1 < -2, 13);
+	assert(-1000, -1000, 14);
+	assert(65535, -1u, 15);
+	assert(0, -1u < 5u, 16);
+	assert(1, -a[0] - -a[1], 17);
+	assert(5, +5, 18);
+	assert(-6, -2 * 3, 19);
+	assert(2, sizeof -a[0], 20);
+
+	return 0;
+}
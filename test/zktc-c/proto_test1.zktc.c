@@ -0,0 +1,81 @@
+int is_even(int n);
+int is_odd(int);
+static int hidden(int x);
+int twice_later(int x);
+struct pair {
+	int a;
+	int b;
+};
+
+struct pair make_pair(int a, int b);
+int sum_all(int n, ...);
+
+int is_even(int n)
+{
+	if (n == 0)
+		return 1;
+	return is_odd(n - 1);
+}
+
+int is_odd(const int n)
+{
+	if (n == 0)
+		return 0;
+	return is_even(n - 1);
+}
+
+int use_local_proto()
+{
+	int local_helper(int);
+	return local_helper(4);
+}
+
+int main()
+{
+	struct pair p;
+
+	assert(1, is_even(10), 1);
+	assert(1, is_odd(7), 2);
+	assert(0, is_odd(8), 3);
+	assert(42, hidden(21), 4);
+	assert(18, twice_later(9), 5);
+	p = make_pair(3, 4);
+	assert(3, p.a, 6);
+	assert(4, p.b, 7);
+	assert(12, sum_all(3, 3, 4, 5), 8);
+	assert(5, use_local_proto(), 9);
+
+	return 0;
+}
+
+static int hidden(int x)
+{
+	return x * 2;
+}
+
+struct pair make_pair(int a, int b)
+{
+	struct pair p;
+	p.a = a;
+	p.b = b;
+	return p;
+}
+
+int sum_all(int n, ...)
+{
+	va_list ap;
+	int total = 0;
+
+	va_start(ap, n);
+	while (n)
+	{
+		total = total + va_arg(ap, int);
+		n = n - 1;
+	}
+	return total;
+}
+
+int local_helper(int x)
+{
+	return x + 1;
+}
@@ -0,0 +1,6 @@
+extern int twice_later(int x);
+
+int twice_later(int x)
+{
+	return x + x;
+}
@@ -0,0 +1,68 @@
+struct item {
+	int id;
+	char tag;
+};
+
+int arr[8];
+struct item items[5];
+
+int length(char *s)
+{
+	char *p = s;
+	while (*p)
+		p = p + 1;
+	return p - s;
+}
+
+int find(int *begin, int *end, int v)
+{
+	int *p;
+	for (p = begin; p < end; p = p + 1)
+		if (*p == v)
+			return p - begin;
+	return -1;
+}
+
+int main()
+{
+	int *p = NULL;
+	int *q = 0;
+	char *hi;
+	char *lo;
+	struct item *a;
+	struct item *b;
+	int i;
+
+	for (i = 0; i < 8; i = i + 1)
+		arr[i] = i * i;
+
+	assert(5, &arr[7] - &arr[2], 1);
+	assert(-5, &arr[2] - &arr[7], 2);
+	assert(8, (arr + 8) - arr, 3);
+	assert(3, find(arr, arr + 8, 9), 4);
+	assert(-1, find(arr, arr + 8, 10), 5);
+	assert(5, length("hello"), 6);
+
+	a = &items[1];
+	b = &items[4];
+	assert(3, b - a, 7);
+	assert(4, sizeof(struct item), 8);
+
+	assert(1, p == NULL, 9);
+	assert(1, q == 0, 10);
+	assert(0, p != q, 11);
+	p = arr;
+	assert(1, p != NULL, 12);
+	assert(1, NULL != p, 13);
+
+	// Addresses above 0x8000 compare as unsigned
+	hi = (char *)0x9000;
+	lo = (char *)0x1000;
+	assert(1, lo < hi, 14);
+	assert(1, hi > lo, 15);
+	assert(0, hi <= lo, 16);
+	assert(1, hi >= lo, 17);
+	assert(2, (int *)0x9004 - (int *)0x9000, 18);
+
+	return 0;
+}
@@ -0,0 +1,74 @@
+int x = 100;
+
+typedef int T;
+
+int sum()
+{
+	int s = 0;
+	for (int i = 0; i < 4; i = i + 1) {
+		int j = i * 2;
+		s = s + j;
+	}
+	for (int i = 10; i < 12; i = i + 1)
+		s = s + i;
+	return s;
+}
+
+int main()
+{
+	int a = 1;
+	assert(100, x, 1);
+	{
+		int x = 2;
+		assert(2, x, 2);
+		{
+			int x = 3;
+			assert(3, x, 3);
+		}
+		assert(2, x, 4);
+	}
+	assert(100, x, 5);
+	{
+		int a = 5;
+		assert(5, a, 6);
+	}
+	assert(1, a, 7);
+	{
+		char T = 7;
+		assert(7, T, 8);
+	}
+	{
+		T b = 9;
+		assert(2, sizeof(b), 9);
+	}
+	{
+		struct s {
+			int a;
+			int b;
+		};
+		struct s v;
+		v.b = 11;
+		assert(4, sizeof(v), 10);
+		{
+			struct s {
+				char c;
+			};
+			assert(1, sizeof(struct s), 11);
+		}
+		assert(4, sizeof(struct s), 12);
+		assert(11, v.b, 13);
+	}
+	assert(33, sum(), 14);
+	{
+		int p = 21;
+		int q = 22;
+		assert(43, p + q, 15);
+	}
+	{
+		int r = 31;
+		assert(31, r, 16);
+	}
+	assert(1, a, 17);
+
+	return 0;
+}
@@ -0,0 +1,7 @@
+// A section is only a label, so its name must be a valid label
+int fast __attribute__((section(".fast")));
+
+int main()
+{
+	return fast;
+}
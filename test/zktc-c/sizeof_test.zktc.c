@@ -0,0 +1,40 @@
+struct packet
+{
+	char kind;
+	int len;
+	char data[5];
+};
+
+typedef struct packet packet_t;
+typedef char name_t[6];
+
+int main()
+{
+	int x[3];
+	int sizeof_x;
+	int alignof_x;
+
+	assert(2, sizeof(int), 1);
+	assert(1, sizeof(char), 2);
+	assert(2, sizeof(int *), 3);
+	assert(8, sizeof(int *[4]), 4);
+	assert(10, sizeof(struct packet), 5);
+	assert(10, sizeof(packet_t), 6);
+	assert(6, sizeof(name_t), 7);
+	assert(12, sizeof(char[2][6]), 8);
+	assert(6, sizeof(x), 9);
+	assert(6, sizeof x, 10);
+	assert(2, _Alignof(int), 11);
+	assert(1, alignof(char[3]), 12);
+	assert(2, _Alignof(struct packet), 13);
+	assert(2, alignof(packet_t *), 14);
+	assert(4, sizeof(x) - sizeof(int), 15);
+
+	// Identifiers may start with a keyword
+	sizeof_x = 3;
+	alignof_x = sizeof sizeof_x;
+	assert(3, sizeof_x, 16);
+	assert(2, alignof_x, 17);
+
+	return 0;
+}
@@ -0,0 +1,44 @@
+static int counter = 10;
+extern int shared;
+extern int table[];
+
+static int helper()
+{
+	return 1;
+}
+
+static int fact(int n)
+{
+	if (n <= 1)
+		return 1;
+	return n * fact(n - 1);
+}
+
+int a_counter()
+{
+	return counter;
+}
+
+int a_helper()
+{
+	return helper() + fact(4);
+}
+
+int a_shared()
+{
+	return shared + table[1];
+}
+
+int next()
+{
+	static int n;
+	n = n + 1;
+	return n;
+}
+
+int next_from_100()
+{
+	static int n = 100;
+	n = n + 1;
+	return n;
+}
@@ -0,0 +1,37 @@
+static int counter = 20;
+int shared = 5;
+int table[3] = {1, 2, 3};
+
+static int helper()
+{
+	return 2;
+}
+
+int main()
+{
+	assert(10, a_counter(), 1);
+	assert(20, counter, 2);
+	assert(25, a_helper(), 3);
+	assert(2, helper(), 4);
+	assert(1, next(), 5);
+	assert(2, next(), 6);
+	assert(101, next_from_100(), 7);
+	assert(102, next_from_100(), 8);
+	{
+		extern int shared;
+		int counter = 3;
+		assert(5, shared, 9);
+		assert(3, counter, 10);
+	}
+	assert(7, a_shared(), 11);
+	assert(6, sizeof(table), 12);
+	{
+		static int s[2] = {7, 8};
+		static char *p = "static";
+		assert(8, s[1], 13);
+		assert(116, p[1], 14);
+	}
+	assert(3, next(), 15);
+
+	return 0;
+}
@@ -0,0 +1,30 @@
+char joined[] = "ab" "cd";
+char *gp = "hello" " " "world";
+char *tail = "world" + 2;
+
+int main()
+{
+	char *a;
+	char *b;
+	char *c;
+
+	a = "hello world";
+	b = "world";
+	c = "hello world";
+
+	assert(5, sizeof(joined), 1);
+	assert(100, joined[3], 2);
+	assert(1, a == c, 3);
+	assert(1, a + 6 == b, 4);
+	assert(1, gp == a, 5);
+	assert(114, tail[0], 6);
+	assert(1, tail == b + 2, 7);
+	assert(12, sizeof("hello"
+			  " "  "world"), 8);
+	assert(49, "\x4" "1"[1], 9);
+	assert(0, "x" ""[1], 10);
+	assert(1, "" == "ld" + 2, 11);
+	assert(108, *"ld", 12);
+
+	return 0;
+}
@@ -0,0 +1,96 @@
+struct point {
+	int x;
+	int y;
+	int z;
+};
+
+struct rec {
+	char name[40];
+	int id;
+};
+
+struct tiny {
+	char a;
+	char b;
+	char c;
+};
+
+struct point points[4];
+struct rec recs[3];
+
+int sum_x(struct point *p, int n)
+{
+	int s = 0;
+	int i;
+	for (i = 0; i < n; i = i + 1)
+		s = s + p[i].x;
+	return s;
+}
+
+int last_y(struct point *p, int n)
+{
+	struct point *q = p + n;
+	return (q - 1)->y;
+}
+
+int main()
+{
+	struct point local[5];
+	struct tiny t[6];
+	struct point *p;
+	int *ip;
+	int i;
+	int zero = 0;
+
+	for (i = 0; i < 4; i = i + 1) {
+		points[i].x = i;
+		points[i].y = i * 10;
+		points[i].z = i * 100;
+	}
+	for (i = 0; i < 5; i = i + 1) {
+		local[i].x = i + 1;
+		local[i].y = i + 2;
+		local[i].z = i + 3;
+	}
+	for (i = 0; i < 6; i = i + 1) {
+		t[i].a = i;
+		t[i].b = i + 10;
+		t[i].c = i + 20;
+	}
+	for (i = 0; i < 3; i = i + 1)
+		recs[i].id = i + 7;
+
+	assert(0, points[zero].x, 1);
+	assert(20, points[2].y, 2);
+	assert(300, points[3].z, 3);
+	assert(6, sum_x(points, 4), 4);
+	assert(30, last_y(points, 4), 5);
+	assert(1, local[zero].x, 6);
+	assert(7, local[4].z, 7);
+	assert(15, sum_x(local, 5), 8);
+	assert(23, t[3].c, 9);
+	assert(15, t[5].b, 10);
+	assert(0, t[zero].a, 11);
+	assert(9, recs[2].id, 12);
+	assert(8, recs[i - 2].id, 13);
+
+	p = points + 1;
+	assert(100, p->z, 14);
+	assert(0, (p - 1)->x, 15);
+	assert(300, (p + 2)->z, 16);
+	assert(20, p[1].y, 17);
+	assert(300, (p + 1 + 1)->z, 18);
+
+	ip = &points[2].y;
+	assert(20, *ip, 19);
+	ip = &local[3].z;
+	assert(6, *ip, 20);
+	*ip = 42;
+	assert(42, local[3].z, 21);
+	assert(6, &local[2].z - &local[0].z, 22);
+	assert(84, (char *)&recs[i - 1] - (char *)&recs[zero], 23);
+	assert(1, &t[4] - &t[3], 24);
+	assert(3, (char *)&t[4].a - (char *)&t[3].a, 25);
+
+	return 0;
+}
@@ -0,0 +1,124 @@
+struct point {
+	int x;
+	int y;
+};
+
+struct big {
+	int a[20];
+	char tag;
+};
+
+struct __attribute__((packed)) odd {
+	char c;
+	int i;
+};
+
+struct point gp = {7, 8};
+
+struct point make_point(int x, int y)
+{
+	struct point p;
+	p.x = x;
+	p.y = y;
+	return p;
+}
+
+int sum(struct point p)
+{
+	return p.x + p.y;
+}
+
+int clobber(struct point p)
+{
+	p.x = 100;
+	return p.x;
+}
+
+struct point add(struct point a, struct point b)
+{
+	struct point r;
+	r.x = a.x + b.x;
+	r.y = a.y + b.y;
+	return r;
+}
+
+struct big fill(int v)
+{
+	struct big b;
+	int i;
+	for (i = 0; i < 20; i = i + 1)
+		b.a[i] = v + i;
+	b.tag = 'z';
+	return b;
+}
+
+int last(int k, struct big b)
+{
+	return b.a[k] + b.tag;
+}
+
+struct point global_copy()
+{
+	return gp;
+}
+
+int main()
+{
+	struct point a;
+	struct point b;
+	struct point c = {1, 2};
+	struct point d = c;
+	struct big x;
+	struct big y;
+	struct odd o1;
+	struct odd o2;
+	struct point *pp;
+
+	a.x = 3;
+	a.y = 4;
+	b = a;
+	assert(3, b.x, 1);
+	assert(4, b.y, 2);
+	a.x = 5;
+	assert(3, b.x, 3);
+	assert(1, d.x, 4);
+	assert(2, d.y, 5);
+
+	assert(9, sum(a), 6);
+	assert(100, clobber(a), 7);
+	assert(5, a.x, 8);
+
+	b = make_point(10, 20);
+	assert(10, b.x, 9);
+	assert(20, b.y, 10);
+	assert(30, make_point(11, 19).x + make_point(0, 19).y, 11);
+	assert(33, sum(make_point(13, 20)), 12);
+
+	c = add(a, b);
+	assert(15, c.x, 13);
+	assert(24, c.y, 14);
+
+	x = fill(5);
+	y = x;
+	x.a[19] = 0;
+	assert(24, y.a[19], 15);
+	assert(122, y.tag, 16);
+	assert(7, fill(5).a[2], 17);
+	assert(145, last(18, y), 18);
+
+	o1.c = 1;
+	o1.i = 0x1234;
+	o2 = o1;
+	assert(1, o2.c, 19);
+	assert(4660, o2.i, 20);
+
+	pp = &a;
+	*pp = global_copy();
+	assert(7, a.x, 21);
+	assert(8, a.y, 22);
+	gp = c;
+	assert(15, gp.x, 23);
+	assert(24, gp.y, 24);
+
+	return 0;
+}
@@ -0,0 +1,13 @@
+struct s {
+	int a;
+};
+
+// A tag can be completed only once in a scope
+struct s {
+	char b;
+};
+
+int main()
+{
+	return 0;
+}
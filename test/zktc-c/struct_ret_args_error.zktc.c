@@ -0,0 +1,18 @@
+struct point {
+	int x;
+	int y;
+};
+
+// The hidden result pointer leaves no register for the third parameter
+struct point make_point3(int x, int y, int z)
+{
+	struct point p;
+	p.x = x + z;
+	p.y = y;
+	return p;
+}
+
+int main()
+{
+	return make_point3(1, 2, 3).x;
+}
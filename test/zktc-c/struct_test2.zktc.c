@@ -94,7 +94,7 @@ int main()
 	assert(6, test4(), 4);
 	assert(8, test5(), 5);
 	assert(2, test6(), 6);
-	assert(3, test7(), 7);
+	assert(4, test7(), 7);
 	assert(0, test8(), 8);
 
 	return 0;
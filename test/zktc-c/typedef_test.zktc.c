@@ -23,9 +23,11 @@ int test2()
 int test3()
 {
 	typedef int t;
-	t t = 1;
+	{
+		t t = 1;
 
-	return t;
+		return t;
+	}
 }
 
 int test4()
@@ -0,0 +1,100 @@
+union word {
+	int w;
+	char b[2];
+};
+
+struct reg {
+	int kind;
+	union {
+		int ival;
+		char cval;
+		struct {
+			char lo;
+			char hi;
+		};
+	};
+	struct {
+		int x;
+		int y;
+	} pos;
+};
+
+struct outer {
+	char tag;
+	struct {
+		int a;
+		union {
+			int b;
+			char c;
+		};
+	};
+	int last;
+};
+
+struct reg regs[3];
+union word gw = {0x1234};
+struct reg greg = {1, {258}, {3, 4}};
+struct outer gout = {.tag = 1, .b = 7, .last = 9};
+
+int get_y(struct reg *r)
+{
+	return r->pos.y;
+}
+
+int main()
+{
+	union word u;
+	struct reg r;
+	struct reg *p = &r;
+	struct outer o;
+	struct outer lo = {2, {3, {4}}, 5};
+	int *ip;
+
+	assert(2, sizeof(union word), 1);
+	assert(8, sizeof(struct reg), 2);
+	assert(8, sizeof(struct outer), 3);
+
+	u.w = 0x0102;
+	assert(2, u.b[0], 4);
+	assert(1, u.b[1], 5);
+	assert(0x34, gw.b[0], 6);
+
+	r.ival = 0x0304;
+	assert(4, r.cval, 7);
+	assert(4, r.lo, 8);
+	assert(3, r.hi, 9);
+	p->hi = 5;
+	assert(0x0504, p->ival, 10);
+
+	r.pos.x = 11;
+	p->pos.y = 12;
+	assert(11, p->pos.x, 11);
+	assert(12, get_y(&r), 12);
+
+	regs[2].pos.y = 13;
+	regs[1].lo = 14;
+	assert(13, get_y(&regs[2]), 13);
+	assert(14, regs[1].ival, 14);
+	ip = &regs[2].pos.x;
+	*ip = 15;
+	assert(15, regs[2].pos.x, 15);
+
+	o.a = 16;
+	o.c = 17;
+	o.last = 18;
+	assert(16, o.a, 16);
+	assert(17, o.b, 17);
+	assert(18, o.last, 18);
+	assert(2, (char *)&o.b - (char *)&o.tag - 2, 19);
+
+	assert(258, greg.ival, 20);
+	assert(1, greg.hi, 21);
+	assert(4, greg.pos.y, 22);
+	assert(7, gout.c, 23);
+	assert(9, gout.last, 24);
+	assert(3, lo.a, 25);
+	assert(4, lo.b, 26);
+	assert(5, lo.last, 27);
+
+	return 0;
+}
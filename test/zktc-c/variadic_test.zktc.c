@@ -0,0 +1,112 @@
+char out[32];
+
+int sum(int n, ...)
+{
+	va_list ap;
+	int total;
+	int i;
+
+	va_start(ap, n);
+	total = 0;
+	for (i = 0; i < n; i = i + 1)
+		total = total + va_arg(ap, int);
+	va_end(ap);
+	return total;
+}
+
+// Minimal formatter supporting %d (0..9999), %c and %s
+int format(char *buf, char *fmt, ...)
+{
+	va_list ap;
+	char *s;
+	int len;
+	int v;
+	int d;
+
+	va_start(ap, fmt);
+	len = 0;
+	while (*fmt)
+	{
+		if (*fmt != '%')
+		{
+			buf[len] = *fmt;
+			len = len + 1;
+		}
+		else
+		{
+			fmt = fmt + 1;
+			if (*fmt == 'c')
+			{
+				buf[len] = va_arg(ap, char);
+				len = len + 1;
+			}
+			if (*fmt == 's')
+			{
+				s = va_arg(ap, char *);
+				while (*s)
+				{
+					buf[len] = *s;
+					len = len + 1;
+					s = s + 1;
+				}
+			}
+			if (*fmt == 'd')
+			{
+				v = va_arg(ap, int);
+				for (d = 1000; d > 0; d = d / 10)
+				{
+					if (v >= d || d == 1)
+					{
+						buf[len] = '0' + v / d % 10;
+						len = len + 1;
+					}
+				}
+			}
+		}
+		fmt = fmt + 1;
+	}
+	buf[len] = 0;
+	va_end(ap);
+	return len;
+}
+
+int second(int n, ...)
+{
+	va_list ap;
+	va_list copy;
+
+	va_start(ap, n);
+	va_arg(ap, int);
+	va_copy(copy, ap);
+	va_arg(ap, int);
+	return va_arg(copy, int) * 10 + va_arg(ap, int);
+}
+
+int main()
+{
+	int (*f)(int, ...) = sum;
+	int va_args = 2;
+	int va_end_mark = 3;
+
+	assert(0, sum(0), 1);
+	assert(5, sum(1, 5), 2);
+	assert(60, sum(3, 10, 20, 30), 3);
+	assert(55, sum(10, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10), 4);
+	assert(9, f(2, 4, 5), 5);
+	assert(21, sum(2, sum(2, 1, 2), sum(3, 4, 5, 9)), 6);
+
+	assert(9, format(out, "x=%d %c%s", 125, 'o', "k!"), 7);
+	assert(120, out[0], 8);
+	assert(49, out[2], 9);
+	assert(53, out[4], 10);
+	assert(111, out[6], 11);
+	assert(33, out[8], 12);
+	assert(0, out[9], 13);
+
+	assert(23, second(3, 1, 2, 3), 14);
+
+	// Identifiers may start with the name of a builtin
+	assert(5, va_args + va_end_mark, 15);
+
+	return 0;
+}